@@ -1,6 +1,8 @@
 //! All svgs are assumed to have a seperate layer called `data`, which contains
-//! groups of paths such as `collision` and `player_start_pos`
+//! groups of paths such as `collision` and `player_start_pos`.
+//! Group labels may have parameters after the name: `<name> key=value ...`
 use std::collections::HashMap;
+use std::str::FromStr;
 
 use svg::node::element::path::{Command, Data, Position};
 use svg::node::element::tag;
@@ -13,7 +15,9 @@ use bevy::reflect::TypeUuid;
 use bevy::utils::BoxedFuture;
 
 use crate::config::conversion::SVG_TO_UNITS;
-use crate::game::physics::shape::{CircleShape, PolyShape, Shape};
+use crate::game::physics::shape::{
+    CircleShape, EllipseShape, PolyShape, Shape,
+};
 
 /// Asset type, which holds additional svg data for an object
 #[derive(Default, Debug, TypeUuid)]
@@ -21,6 +25,8 @@ use crate::game::physics::shape::{CircleShape, PolyShape, Shape};
 pub struct SvgData {
     pub size: Vec2,
     pub groups: HashMap<String, Vec<Shape>>,
+    /// Points of the paths in `path:<name>` groups
+    pub paths: HashMap<String, Vec<Vec2>>,
 }
 
 #[derive(Default)]
@@ -30,6 +36,49 @@ const LABEL_ATTR: &str = "inkscape:label";
 const DATA_LABEL: &str = "data";
 
 pub const COLLISION: &str = "collision";
+/// Groups labeled `trigger:<name>` define trigger zones
+pub const TRIGGER_PREFIX: &str = "trigger:";
+/// Groups labeled `force:<name>` define force fields
+pub const FORCE_PREFIX: &str = "force:";
+/// Groups labeled `surface:<name>` define surfaces
+pub const SURFACE_PREFIX: &str = "surface:";
+/// Groups labeled `path:<name>` contain a single (not necessarily closed
+/// or convex) path, which is stored as points in [SvgData::paths]
+pub const PATH_PREFIX: &str = "path:";
+/// Groups labeled `obstacle:<name>` define obstacles,
+/// which follow the path `path:<name>`
+pub const OBSTACLE_PREFIX: &str = "obstacle:";
+
+/// Group label, split into the name and `key=value` parameters
+#[derive(Debug, Clone)]
+pub struct DataLabel<'a> {
+    pub name: &'a str,
+    pub params: HashMap<&'a str, &'a str>,
+}
+
+impl<'a> DataLabel<'a> {
+    pub fn parse(label: &'a str) -> Self {
+        let mut words = label.split_whitespace();
+        let name = words.next().unwrap_or("");
+        let params = words
+            .map(|param| {
+                param.split_once('=').unwrap_or_else(|| {
+                    panic!("Invalid parameter {} in {}", param, label)
+                })
+            })
+            .collect();
+        Self { name, params }
+    }
+
+    /// Returns the parsed value of the parameter `key`, if it's present
+    pub fn param<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.params.get(key).map(|value| {
+            value.parse().unwrap_or_else(|_| {
+                panic!("Invalid value of {}: {}", key, value)
+            })
+        })
+    }
+}
 
 enum State {
     OutsideData,
@@ -49,6 +98,7 @@ impl AssetLoader for SvgDataLoader {
                 svg::read(std::str::from_utf8(bytes).unwrap()).unwrap();
             let mut size: Option<Vec2> = None;
             let mut groups: HashMap<String, Vec<Shape>> = HashMap::new();
+            let mut paths: HashMap<String, Vec<Vec2>> = HashMap::new();
             let mut state = State::OutsideData;
             let mut cur_group_name: Option<String> = None;
             let mut cur_group: Option<Vec<Shape>> = None;
@@ -97,10 +147,11 @@ impl AssetLoader for SvgDataLoader {
                             }
                             State::InsideGroup => {
                                 // Finish the current group
-                                groups.insert(
-                                    cur_group_name.take().unwrap(),
-                                    cur_group.take().unwrap(),
-                                );
+                                let group_name = cur_group_name.take().unwrap();
+                                let group = cur_group.take().unwrap();
+                                if !group_name.starts_with(PATH_PREFIX) {
+                                    groups.insert(group_name, group);
+                                }
                                 state = State::InsideData;
                             }
                         }
@@ -116,14 +167,28 @@ impl AssetLoader for SvgDataLoader {
                                     attr.get("transform").is_none(),
                                     "Transform attribute present in svg"
                                 );
-                                // Add the path to the current group
-                                cur_group.as_mut().unwrap().push(
-                                    path_to_shape(
-                                        Data::parse(attr.get("d").unwrap())
-                                            .unwrap(),
-                                        size.unwrap(),
-                                    ),
+                                let points = path_to_points(
+                                    Data::parse(attr.get("d").unwrap())
+                                        .unwrap(),
+                                    size.unwrap(),
                                 );
+                                let group_name =
+                                    cur_group_name.as_ref().unwrap();
+                                if group_name.starts_with(PATH_PREFIX) {
+                                    // Keep the path as is
+                                    let prev = paths
+                                        .insert(group_name.clone(), points);
+                                    assert!(
+                                        prev.is_none(),
+                                        "Several paths in {}",
+                                        group_name
+                                    );
+                                } else {
+                                    // Add the path to the current group
+                                    cur_group.as_mut().unwrap().push(
+                                        Shape::Poly(PolyShape::new(points)),
+                                    );
+                                }
                             }
                         }
                     }
@@ -137,6 +202,12 @@ impl AssetLoader for SvgDataLoader {
                                 attr.get("transform").is_none(),
                                 "Transform attribute present in svg"
                             );
+                            let group_name = cur_group_name.as_ref().unwrap();
+                            assert!(
+                                !group_name.starts_with(PATH_PREFIX),
+                                "circle in a path group {}",
+                                group_name
+                            );
                             cur_group.as_mut().unwrap().push(Shape::Circle(
                                 CircleShape::new(
                                     attr.get("r")
@@ -161,6 +232,38 @@ impl AssetLoader for SvgDataLoader {
                             ));
                         }
                     },
+                    Event::Tag(tag::Ellipse, _, attr) => match state {
+                        State::OutsideData => {}
+                        State::InsideData => {
+                            panic!("ellipse in data without a group");
+                        }
+                        State::InsideGroup => {
+                            assert!(
+                                attr.get("transform").is_none(),
+                                "Transform attribute present in svg"
+                            );
+                            let group_name = cur_group_name.as_ref().unwrap();
+                            assert!(
+                                !group_name.starts_with(PATH_PREFIX),
+                                "ellipse in a path group {}",
+                                group_name
+                            );
+                            let get = |name: &str| {
+                                attr.get(name).unwrap().parse::<f32>().unwrap()
+                            };
+                            cur_group.as_mut().unwrap().push(Shape::Ellipse(
+                                EllipseShape::new(
+                                    Vec2::new(get("rx"), get("ry"))
+                                        * SVG_TO_UNITS,
+                                    to_centered(
+                                        Vec2::new(get("cx"), get("cy")),
+                                        size.unwrap(),
+                                    ),
+                                    0.0,
+                                ),
+                            ));
+                        }
+                    },
                     Event::Tag(tag::Rectangle, _, _) => match state {
                         State::OutsideData => {}
                         State::InsideData => {
@@ -177,6 +280,7 @@ impl AssetLoader for SvgDataLoader {
             load_context.set_default_asset(LoadedAsset::new(SvgData {
                 size: size.unwrap(),
                 groups,
+                paths,
             }));
             Ok(())
         })
@@ -200,7 +304,7 @@ fn to_centered(mut point: Vec2, size: Vec2) -> Vec2 {
     Vec2::new(point.x - size.x * 0.5, size.y * 0.5 - point.y)
 }
 
-fn path_to_shape(data: Data, size: Vec2) -> Shape {
+fn path_to_points(data: Data, size: Vec2) -> Vec<Vec2> {
     let mut points = Vec::with_capacity(data.len());
     let mut point: Option<Vec2> = None;
     for command in data.iter() {
@@ -243,5 +347,5 @@ fn path_to_shape(data: Data, size: Vec2) -> Shape {
         }
     }
 
-    Shape::Poly(PolyShape::new(points))
+    points
 }
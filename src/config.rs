@@ -14,23 +14,85 @@ pub mod keybinds {
         pub const LEFT: KeyCode = KeyCode::A;
         pub const DOWN: KeyCode = KeyCode::S;
     }
+
+    /// Physics time controls, available with the `debug` feature
+    pub mod debug {
+        use super::KeyCode;
+
+        pub const PAUSE_PHYSICS: KeyCode = KeyCode::P;
+        pub const STEP_PHYSICS: KeyCode = KeyCode::Period;
+        pub const SLOW_DOWN_PHYSICS: KeyCode = KeyCode::Minus;
+        pub const SPEED_UP_PHYSICS: KeyCode = KeyCode::Equals;
+    }
 }
 
 /// Player rotation speed (in radians/s)
 pub const ROTATION_SPEED: f32 = 0.25 * (PI * 2.0); // 0.25
 
 pub mod physics {
+    /// Length of one physics step (in seconds)
+    pub const TIMESTEP: f32 = 1.0 / 60.0;
+    /// Physics steps, done in one frame at most.
+    /// If a frame is longer, the physics slows down
+    pub const MAX_STEPS_PER_FRAME: u32 = 5;
+    /// Factor, by which the debug keybinds change the physics time scale
+    pub const DEBUG_TIME_SCALE_FACTOR: f32 = 2.0;
     /// No physical object in the game can exceed this speed
     /// All speeds are in (world points)/second
     pub const GLOBAL_MAX_VEL: f32 = 500.0;
+    /// No physical object in the game can exceed this angular speed
+    /// (in radians/s)
+    pub const GLOBAL_MAX_ANGULAR_VEL: f32 = 4.0 * (super::PI * 2.0);
     /// Player acceleration
     pub const PLAYER_ACCEL: f32 = 700.0; // 1000.0
     /// Player maximum speed
     pub const PLAYER_MAX_VEL: f32 = 320.0; // 320.0
     /// Player friction coefficient
     pub const PLAYER_FRICTION_COEFF: f32 = 1.0; // 5.0
+    /// Player angular friction coefficient, damps the spin from collisions
+    pub const PLAYER_ANGULAR_FRICTION_COEFF: f32 = 3.0;
+    /// Player mass
+    pub const PLAYER_MASS: f32 = 1.0;
+
+    /// Speed of obstacles along their paths, if it's not set in the svg
+    pub const OBSTACLE_SPEED: f32 = 100.0;
 
+    /// Iterations of the contact solver over velocities and positions
+    pub const SOLVER_VELOCITY_ITERATIONS: usize = 8;
+    pub const SOLVER_POSITION_ITERATIONS: usize = 4;
+    /// Penetration (in world points), that is left after pushing colliding
+    /// objects apart, so that resting contacts persist between steps
+    pub const PENETRATION_SLOP: f32 = 0.01;
+    /// Part of the penetration, that is corrected in one iteration
+    pub const POSITION_CORRECTION: f32 = 0.8;
+    /// Objects don't bounce off each other at lower approach speeds
+    pub const RESTITUTION_MIN_SPEED: f32 = 10.0;
+
+    /// Distance, that sliding objects keep from solid colliders
+    pub const SLIDE_SKIN_WIDTH: f32 = 0.1;
+    /// Times a sliding object can change direction in one step
+    pub const SLIDE_MAX_ITERATIONS: u32 = 4;
+
+    /// Dynamic objects, that are slower than this (and the angular speed
+    /// below) for `SLEEP_TIME` seconds, fall asleep
+    pub const SLEEP_MAX_VEL: f32 = 2.0;
+    pub const SLEEP_MAX_ANGULAR_VEL: f32 = 0.05;
+    pub const SLEEP_TIME: f32 = 0.5;
+
+    /// Restitution of colliders without a `PhysicsMaterial`
     pub const BOUNCINESS: f32 = 0.5;
+    /// Surface friction coefficient of colliders without a `PhysicsMaterial`
+    pub const SURFACE_FRICTION: f32 = 0.0;
+
+    /// Collision layers, used as bits of `CollisionLayers` masks
+    pub mod layers {
+        pub const DEFAULT: u32 = 1 << 0;
+        pub const PLAYER: u32 = 1 << 1;
+        pub const TRIGGER: u32 = 1 << 2;
+        pub const FORCE_FIELD: u32 = 1 << 3;
+        pub const SURFACE: u32 = 1 << 4;
+        pub const ALL: u32 = u32::MAX;
+    }
 }
 
 /// Z depths of entities
@@ -1,6 +1,7 @@
 use bevy::prelude::*;
 
-use crate::game::physics::Collider;
+use crate::config::{keybinds, physics};
+use crate::game::physics::{Collider, PhysicsTime};
 use crate::game::player::Player;
 
 pub fn test_system(player_query: Query<&Collider, With<Player>>) {
@@ -16,3 +17,24 @@ pub fn test_system(player_query: Query<&Collider, With<Player>>) {
         }
     }
 }
+
+/// Pause, step and change the time scale of the physics with debug keybinds
+pub fn physics_time_control(
+    input: Res<Input<KeyCode>>,
+    mut physics_time: ResMut<PhysicsTime>,
+) {
+    if input.just_pressed(keybinds::debug::PAUSE_PHYSICS) {
+        physics_time.toggle_pause();
+    }
+    // Steps, requested while running, would be done after the next pause
+    if input.just_pressed(keybinds::debug::STEP_PHYSICS) && physics_time.paused
+    {
+        physics_time.step(1);
+    }
+    if input.just_pressed(keybinds::debug::SLOW_DOWN_PHYSICS) {
+        physics_time.time_scale /= physics::DEBUG_TIME_SCALE_FACTOR;
+    }
+    if input.just_pressed(keybinds::debug::SPEED_UP_PHYSICS) {
+        physics_time.time_scale *= physics::DEBUG_TIME_SCALE_FACTOR;
+    }
+}
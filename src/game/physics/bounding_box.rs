@@ -28,4 +28,50 @@ impl BoundingBox {
                 (other.min.y, other.max.y),
             )
     }
+
+    /// Center of the box.
+    #[inline]
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Returns whether `point` is inside of the box.
+    #[inline]
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.min.cmple(point).all() && point.cmple(self.max).all()
+    }
+
+    /// Returns the smallest box, containing both boxes.
+    pub fn merged(&self, other: &BoundingBox) -> Self {
+        Self::from_min_max(self.min.min(other.min), self.max.max(other.max))
+    }
+
+    /// Returns whether the ray from `origin` along `dir` hits the box
+    /// within `max_dist`.
+    pub fn intersects_ray(
+        &self,
+        origin: Vec2,
+        dir: Vec2,
+        max_dist: f32,
+    ) -> bool {
+        let (mut enter, mut exit) = (0.0, max_dist);
+        for axis in 0..2 {
+            if dir[axis] == 0.0 {
+                if origin[axis] < self.min[axis]
+                    || origin[axis] > self.max[axis]
+                {
+                    return false;
+                }
+                continue;
+            }
+            let dist1 = (self.min[axis] - origin[axis]) / dir[axis];
+            let dist2 = (self.max[axis] - origin[axis]) / dir[axis];
+            enter = f32::max(enter, dist1.min(dist2));
+            exit = f32::min(exit, dist1.max(dist2));
+            if enter > exit {
+                return false;
+            }
+        }
+        true
+    }
 }
@@ -7,6 +7,8 @@ use bevy::{
 };
 
 use super::{
+    gjk::{self, ClosestPoints},
+    layers::CollisionLayers,
     shape::{Shape, ShiftedShape},
     util::{update_max_point, update_min_point},
     BoundingBox,
@@ -17,20 +19,82 @@ use super::{
 pub struct Collision {
     pub other_entity: Entity,
     pub mpv: Vec2,
+    /// Approximate world-space point of contact
+    pub contact: Vec2,
+    /// Speed, with which the colliders were approaching each other
+    /// at the contact point before the collision was resolved
+    pub impact_speed: f32,
+}
+
+/// Shape of a collider, placed relative to the collider's entity
+#[derive(Clone, Debug)]
+struct ColliderShape {
+    shape: ShiftedShape,
+    /// Offset and rotation of the shape relative to the entity
+    local_transform: Transform,
+    /// Disabled shapes don't collide and aren't found by queries
+    enabled: bool,
+}
+
+impl ColliderShape {
+    fn new(shape: Shape, offset: Vec2, rotation: f32) -> Self {
+        Self {
+            shape: ShiftedShape::from_original_shape(shape),
+            local_transform: Self::local_transform(offset, rotation),
+            enabled: true,
+        }
+    }
+
+    fn local_transform(offset: Vec2, rotation: f32) -> Transform {
+        Transform {
+            translation: offset.extend(0.0),
+            rotation: Quat::from_rotation_z(rotation),
+            ..Default::default()
+        }
+    }
+}
+
+/// Shapes of a child's collider, that are a part of its parent's collider
+#[derive(Clone, Debug)]
+pub(super) struct AttachedPart {
+    /// Child's transform relative to the parent
+    transform: Transform,
+    shapes: SmallVec<[ColliderShape; 2]>,
 }
 
 /// Component, indicating that this entity can collide with other colliders.
+/// The collider is placed with the entity's global transform, so it can be
+/// on a child entity.
+/// It may be a compound of several shapes, each with its own offset and
+/// rotation relative to the entity, which can be enabled and disabled
+/// by their indices in the order they were added.
 #[derive(Component, Clone, Debug, Default)]
 pub struct Collider {
     /// Shapes that comprise the collider.
-    shapes: SmallVec<[ShiftedShape; 2]>,
+    shapes: SmallVec<[ColliderShape; 2]>,
+    /// Shapes of the child colliders, attached to this one
+    attached_parts: Vec<AttachedPart>,
     /// Solid colliders are bounced off of, nonsolid can be passed through.
     pub solid: bool,
-    /// Collision instances that happened within the last frame.
+    /// Whether the collider is a part of its parent's collider, e.g. a shield,
+    /// held by the player. Its shapes collide as the parent's ones, and the
+    /// collisions are stored in the parent's collider.
+    /// The parent must have a collider
+    pub attached: bool,
+    /// Layers that the collider belongs to and collides with
+    pub layers: CollisionLayers,
+    /// Collision instances that happened within the last frame, the latest
+    /// one with each other entity over all physics steps of the frame.
     /// This is cleared and set in `physics::update()` system each frame.
     recent_collisions: Vec<Collision>,
-    /// AABB, used for collision optimization
-    bounding_box: BoundingBox,
+    /// Collision instances of the last physics step
+    step_collisions: Vec<Collision>,
+    /// AABB, used for collision optimization,
+    /// or `None` if the collider has no enabled shapes
+    bounding_box: Option<BoundingBox>,
+    /// Transform, with which the global shapes were computed,
+    /// or `None` if the shapes have changed since then
+    cached_transform: Option<Transform>,
 }
 
 impl Collider {
@@ -40,11 +104,16 @@ impl Collider {
         Self {
             shapes: shapes
                 .into_iter()
-                .map(ShiftedShape::from_original_shape)
+                .map(|shape| ColliderShape::new(shape, Vec2::ZERO, 0.0))
                 .collect(),
+            attached_parts: Vec::new(),
             solid: false,
+            attached: false,
+            layers: CollisionLayers::default(),
             recent_collisions: Vec::new(),
-            bounding_box: BoundingBox::default(),
+            step_collisions: Vec::new(),
+            bounding_box: None,
+            cached_transform: None,
         }
     }
 
@@ -55,81 +124,421 @@ impl Collider {
         }
     }
 
+    /// Returns the collider with `layers` set
+    pub fn with_layers(mut self, layers: CollisionLayers) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Returns the collider with `shape` added, which is moved by `offset`
+    /// and rotated by `rotation` (in radians, counter-clockwise) around
+    /// the entity's origin
+    pub fn with_shape(
+        mut self,
+        shape: Shape,
+        offset: Vec2,
+        rotation: f32,
+    ) -> Self {
+        self.add_shape(shape, offset, rotation);
+        self
+    }
+
+    /// Adds `shape` like [Collider::with_shape] and returns its index
+    pub fn add_shape(
+        &mut self,
+        shape: Shape,
+        offset: Vec2,
+        rotation: f32,
+    ) -> usize {
+        self.shapes
+            .push(ColliderShape::new(shape, offset, rotation));
+        self.cached_transform = None;
+        self.shapes.len() - 1
+    }
+
+    /// Number of the collider's own shapes, including the disabled ones
+    pub fn shape_count(&self) -> usize {
+        self.shapes.len()
+    }
+
+    /// Moves the shape at `index` to `offset` and `rotation`,
+    /// see [Collider::with_shape]
+    pub fn set_shape_placement(
+        &mut self,
+        index: usize,
+        offset: Vec2,
+        rotation: f32,
+    ) {
+        self.shapes[index].local_transform =
+            ColliderShape::local_transform(offset, rotation);
+        self.cached_transform = None;
+    }
+
+    pub fn is_shape_enabled(&self, index: usize) -> bool {
+        self.shapes[index].enabled
+    }
+
+    /// Enables or disables the shape at `index`.
+    /// Takes effect with the next physics step
+    pub fn set_shape_enabled(&mut self, index: usize, enabled: bool) {
+        if self.shapes[index].enabled != enabled {
+            self.shapes[index].enabled = enabled;
+            self.cached_transform = None;
+        }
+    }
+
+    /// Returns the collider, attached to its parent's collider
+    pub fn attached_to_parent(mut self) -> Self {
+        self.attached = true;
+        self
+    }
+
     /// Return all collision instances that happened during this frame
     #[inline]
     pub fn get_recent_collisions(&self) -> &[Collision] {
         &self.recent_collisions
     }
 
+    /// Collision instances of the last physics step
+    #[inline]
+    pub(super) fn step_collisions(&self) -> &[Collision] {
+        &self.step_collisions
+    }
+
+    /// Adds a collision of this physics step
     #[inline]
     pub(super) fn add_recent_collision(
         &mut self,
         other_entity: Entity,
         mpv: Vec2,
+        contact: Vec2,
+        impact_speed: f32,
     ) {
-        self.recent_collisions.push(Collision { other_entity, mpv });
+        let collision = Collision {
+            other_entity,
+            mpv,
+            contact,
+            impact_speed,
+        };
+        self.step_collisions.push(collision);
+        self.add_frame_collision(collision);
+    }
+
+    /// Adds `collision` to the collisions of the frame, replacing
+    /// the earlier one with the same entity
+    fn add_frame_collision(&mut self, collision: Collision) {
+        match self
+            .recent_collisions
+            .iter_mut()
+            .find(|recent| recent.other_entity == collision.other_entity)
+        {
+            Some(recent) => *recent = collision,
+            None => self.recent_collisions.push(collision),
+        }
+    }
+
+    /// Clears the collisions of the frame before its first physics step
+    pub(super) fn clear_recent_collisions(&mut self) {
+        self.recent_collisions.clear();
+    }
+
+    /// Whether the global shapes are up to date for `transform`
+    pub(super) fn is_placed_at(&self, transform: &Transform) -> bool {
+        self.cached_transform == Some(*transform)
+    }
+
+    /// Keeps only the collisions of the last step, for which `keep`
+    /// returns `true`, as the collisions of this step
+    pub(super) fn keep_step_collisions(
+        &mut self,
+        keep: impl FnMut(&Collision) -> bool,
+    ) {
+        self.step_collisions.retain(keep);
+        for index in 0..self.step_collisions.len() {
+            self.add_frame_collision(self.step_collisions[index]);
+        }
     }
 
     /// Update the collider's global shapes and bounding box.
-    /// Also clears `recent_collisions`
+    /// Also clears the collisions of the last step
     pub(super) fn update(&mut self, transform: &Transform) {
+        self.update_shapes(transform);
+        self.step_collisions.clear();
+    }
+
+    /// Update the collider's global shapes and bounding box.
+    /// Nothing is recomputed if neither `transform` nor the shapes
+    /// have changed since the last update, e.g. for static walls
+    pub(super) fn update_shapes(&mut self, transform: &Transform) {
         assert!(!self.shapes.is_empty());
+        if self.cached_transform == Some(*transform) {
+            return;
+        }
+        self.cached_transform = Some(*transform);
         // Bottom left of the bounding box
         let mut min_point: Vec2 = Vec2::new(f32::INFINITY, f32::INFINITY);
         // Top right of the bounding box
         let mut max_point: Vec2 =
             Vec2::new(f32::NEG_INFINITY, f32::NEG_INFINITY);
-        // Iterate and update all shapes according to current `transform`,
-        // while also capturing min and max points
-        for shape in &mut self.shapes {
-            let (shape_min_point, shape_max_point) = shape.update(transform);
+        let mut has_enabled_shapes = false;
+        // Iterate and update all enabled shapes according to current
+        // `transform`, while also capturing min and max points
+        let shapes = self.shapes.iter_mut().map(|shape| (*transform, shape));
+        let attached_shapes = self.attached_parts.iter_mut().flat_map(|part| {
+            let part_transform = transform.mul_transform(part.transform);
+            part.shapes
+                .iter_mut()
+                .map(move |shape| (part_transform, shape))
+        });
+        for (transform, shape) in shapes
+            .chain(attached_shapes)
+            .filter(|(_, shape)| shape.enabled)
+        {
+            let transform = transform.mul_transform(shape.local_transform);
+            let (shape_min_point, shape_max_point) =
+                shape.shape.update(&transform);
             update_min_point(&mut min_point, shape_min_point);
             update_max_point(&mut max_point, shape_max_point);
+            has_enabled_shapes = true;
         }
 
-        self.bounding_box = BoundingBox::from_min_max(min_point, max_point);
-        self.recent_collisions.clear();
+        // A collider without enabled shapes takes part in no collisions
+        self.bounding_box = if has_enabled_shapes {
+            Some(BoundingBox::from_min_max(min_point, max_point))
+        } else {
+            None
+        };
+    }
+
+    /// Part of the parent's collider, made of this collider's shapes,
+    /// where `transform` is this collider's transform relative to the parent
+    pub(super) fn attached_part(&self, transform: Transform) -> AttachedPart {
+        AttachedPart {
+            transform,
+            shapes: self.shapes.clone(),
+        }
     }
 
+    /// Replaces the parts, attached by the child colliders
+    pub(super) fn set_attached_parts(&mut self, parts: Vec<AttachedPart>) {
+        // Most colliders have no attached parts, so they keep their cache
+        if !(parts.is_empty() && self.attached_parts.is_empty()) {
+            self.attached_parts = parts;
+            self.cached_transform = None;
+        }
+    }
+
+    /// Own and attached enabled shapes
+    fn all_shapes(&self) -> impl Iterator<Item = &ShiftedShape> + Clone {
+        self.shapes
+            .iter()
+            .chain(
+                self.attached_parts
+                    .iter()
+                    .flat_map(|part| part.shapes.iter()),
+            )
+            .filter(|shape| shape.enabled)
+            .map(|shape| &shape.shape)
+    }
+
+    /// AABB of the current global shapes,
+    /// or `None` if the collider has no enabled shapes
     #[inline]
-    fn update_mpv(cur_mpv: &mut Option<Vec2>, mpv: Vec2) {
-        if let Some(cur_mpv) = cur_mpv {
-            if mpv.length_squared() > cur_mpv.length_squared() {
-                *cur_mpv = mpv;
-            }
+    pub(super) fn bounding_box(&self) -> Option<&BoundingBox> {
+        self.bounding_box.as_ref()
+    }
+
+    /// Returns whether `point` is inside of any of the global shapes
+    pub(super) fn contains_point(&self, point: Vec2) -> bool {
+        matches!(&self.bounding_box, Some(bounding_box)
+            if bounding_box.contains(point))
+            && self.all_shapes().any(|shape| shape.contains_point(point))
+    }
+
+    /// Returns `Some((distance, normal))` of the closest hit of the ray from
+    /// `origin` along the normalized `dir` within `max_dist`, or `None`
+    pub(super) fn cast_ray(
+        &self,
+        origin: Vec2,
+        dir: Vec2,
+        max_dist: f32,
+    ) -> Option<(f32, Vec2)> {
+        if !self
+            .bounding_box
+            .as_ref()?
+            .intersects_ray(origin, dir, max_dist)
+        {
+            return None;
+        }
+        self.all_shapes()
+            .filter_map(|shape| shape.cast_ray(origin, dir, max_dist))
+            .min_by(|(dist1, _), (dist2, _)| dist1.partial_cmp(dist2).unwrap())
+    }
+
+    /// Returns the closest points between the global shapes of `self` and
+    /// `other` (see [gjk::closest_points]), or `None` if either of them
+    /// has no enabled shapes
+    pub(super) fn closest_points(
+        &self,
+        other: &Collider,
+    ) -> Option<ClosestPoints> {
+        Itertools::cartesian_product(self.all_shapes(), other.all_shapes())
+            .map(|(shape1, shape2)| {
+                gjk::closest_points(shape1.shape(), shape2.shape())
+            })
+            .min_by(|closest1, closest2| {
+                closest1
+                    .signed_distance
+                    .partial_cmp(&closest2.signed_distance)
+                    .unwrap()
+            })
+    }
+
+    /// Distance, that `self` can be moved along the normalized `dir` without
+    /// overlapping `other`, at least, with the closest points of the pair of
+    /// shapes, that limits it. It's 0 if the colliders already overlap,
+    /// and `None` if moving along `dir` can't make them overlap.
+    /// The global shapes of both colliders have to be up to date
+    pub(super) fn cast_bound(
+        &self,
+        other: &Collider,
+        dir: Vec2,
+    ) -> Option<(f32, ClosestPoints)> {
+        Itertools::cartesian_product(self.all_shapes(), other.all_shapes())
+            .filter_map(|(shape1, shape2)| {
+                let closest =
+                    gjk::closest_points(shape1.shape(), shape2.shape());
+                if closest.signed_distance < 0.0 {
+                    return Some((0.0, closest));
+                }
+                // Convex shapes are separated by the plane through their
+                // closest points, so moving parallel to it or away from it
+                // never reaches the other shape
+                let approach = dir.dot(closest.normal);
+                (approach > 0.0)
+                    .then(|| (closest.signed_distance / approach, closest))
+            })
+            .min_by(|(bound1, _), (bound2, _)| {
+                bound1.partial_cmp(bound2).unwrap()
+            })
+    }
+
+    /// Total area of the current global shapes
+    pub(super) fn area(&self) -> f32 {
+        self.all_shapes().map(|shape| shape.shape().area()).sum()
+    }
+
+    /// Moment of inertia of the collider with total `mass` about `pivot`,
+    /// assuming uniform density. Uses the current global shapes.
+    pub(super) fn moment_of_inertia(&self, mass: f32, pivot: Vec2) -> f32 {
+        let area = self.area();
+        let polar_moment: f32 = self
+            .all_shapes()
+            .map(|shape| shape.shape().polar_moment(pivot))
+            .sum();
+        if area > 0.0 {
+            mass * polar_moment / area
         } else {
-            *cur_mpv = Some(mpv)
+            0.0
         }
     }
 
-    /// Returns `Some(mpv)` if two colliders are colliding and `None` otherwise, where
-    /// `mpv` is the Minimum Push Vector to push `other` out of `self`.
+    #[inline]
+    fn update_mpv(
+        cur: &mut Option<(Vec2, Vec2)>,
+        mpv: Vec2,
+        shape1: &ShiftedShape,
+        shape2: &ShiftedShape,
+    ) {
+        if let Some((cur_mpv, _)) = cur {
+            if mpv.length_squared() <= cur_mpv.length_squared() {
+                return;
+            }
+        }
+        // The contact lies between the deepest points of both shapes
+        let contact = (shape1.support(mpv) + shape2.support(-mpv)) * 0.5;
+        *cur = Some((mpv, contact));
+    }
+
+    /// Returns `Some((mpv, contact))` if two colliders are colliding and `None`
+    /// otherwise, where `mpv` is the Minimum Push Vector to push `other` out of
+    /// `self` and `contact` is the approximate point of contact.
     ///
     /// TODO: maybe optimize the Circle to Circle case
-    pub(super) fn process_collision(&self, other: &Collider) -> Option<Vec2> {
-        // Bounding box optimization
-        if !self.bounding_box.collides(&other.bounding_box) {
+    pub(super) fn process_collision(
+        &self,
+        other: &Collider,
+    ) -> Option<(Vec2, Vec2)> {
+        // Colliders on layers that don't interact never collide
+        if !self.layers.interacts_with(&other.layers) {
+            return None;
+        }
+
+        // Bounding box optimization, which also skips colliders
+        // without enabled shapes
+        if !self
+            .bounding_box
+            .as_ref()?
+            .collides(other.bounding_box.as_ref()?)
+        {
             return None;
         }
 
         // Minimum push vector, which is maximum over all mpvs between all shapes
-        let mut cur_mpv: Option<Vec2> = None;
+        let mut cur: Option<(Vec2, Vec2)> = None;
         // Iterate over all pairs of shapes
         let mut normal_buf = Vec::new();
-        for (shape1, shape2) in Itertools::cartesian_product(
-            self.shapes.iter(),
-            other.shapes.iter(),
-        ) {
+        for (shape1, shape2) in
+            Itertools::cartesian_product(self.all_shapes(), other.all_shapes())
+        {
             // If the shapes collide, update the mpv
             if let Some(mpv) = shape1.process_collision(shape2, &mut normal_buf)
             {
-                Self::update_mpv(&mut cur_mpv, mpv);
+                Self::update_mpv(&mut cur, mpv, shape1, shape2);
             }
         }
 
         // TODO: think about whether we actually need this
         // If the mpv is very small, it's as if there was no collision
         const EPS: f32 = 1e-7;
-        cur_mpv.filter(|cur_mpv| !cur_mpv.abs_diff_eq(Vec2::ZERO, EPS))
+        cur.filter(|(cur_mpv, _)| !cur_mpv.abs_diff_eq(Vec2::ZERO, EPS))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::physics::test_util::square;
+
+    #[test]
+    fn compound_test() {
+        let mut collider =
+            Collider::solid_from_shapes(vec![square(Vec2::ZERO, 1.0)])
+                .with_shape(
+                    square(Vec2::ZERO, 1.0),
+                    Vec2::new(5.0, 0.0),
+                    std::f32::consts::FRAC_PI_4,
+                );
+        collider.update(&Transform::from_xyz(0.0, 10.0, 0.0));
+        // The corner of the rotated square
+        assert!(collider.contains_point(Vec2::new(6.3, 10.0)));
+        assert!(!collider.contains_point(Vec2::new(5.9, 10.9)));
+
+        collider.set_shape_enabled(1, false);
+        collider.update(&Transform::from_xyz(0.0, 10.0, 0.0));
+        assert!(!collider.contains_point(Vec2::new(6.3, 10.0)));
+        assert!(collider.contains_point(Vec2::new(0.5, 10.5)));
+        assert!((collider.bounding_box().unwrap().max.x - 1.0).abs() < 1e-4);
+
+        // A shape, added to a collider that doesn't move
+        let index = collider.add_shape(
+            square(Vec2::ZERO, 1.0),
+            Vec2::new(-5.0, 0.0),
+            0.0,
+        );
+        assert_eq!(index, 2);
+        collider.update(&Transform::from_xyz(0.0, 10.0, 0.0));
+        assert!(collider.contains_point(Vec2::new(-5.5, 10.5)));
+        assert!((collider.bounding_box().unwrap().min.x + 6.0).abs() < 1e-4);
     }
 }
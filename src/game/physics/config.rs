@@ -0,0 +1,64 @@
+use crate::config::physics;
+
+use super::PhysicsMaterial;
+
+/// Resource with the parameters of the physics, that can be changed
+/// at runtime. Defaults to the constants in [physics]
+#[derive(Clone, Debug)]
+pub struct PhysicsConfig {
+    /// Length of one physics step (in seconds)
+    pub timestep: f32,
+    /// Physics steps, done in one frame at most.
+    /// If a frame is longer, the physics slows down
+    pub max_steps_per_frame: u32,
+    /// No dynamic object can exceed this speed
+    pub global_max_vel: f32,
+    /// No dynamic object can exceed this angular speed (in radians/s)
+    pub global_max_angular_vel: f32,
+    /// Restitution of colliders without a `PhysicsMaterial`
+    pub bounciness: f32,
+    /// Surface friction coefficient of colliders without a `PhysicsMaterial`
+    pub surface_friction: f32,
+    /// Iterations of the contact solver over velocities and positions
+    pub velocity_iterations: usize,
+    pub position_iterations: usize,
+    /// Penetration, that is left after pushing colliding objects apart
+    pub penetration_slop: f32,
+    /// Part of the penetration, that is corrected in one iteration
+    pub position_correction: f32,
+    /// Objects don't bounce off each other at lower approach speeds
+    pub restitution_min_speed: f32,
+    /// Dynamic objects, that are slower than this (and the angular speed
+    /// below) for `sleep_time` seconds, fall asleep
+    pub sleep_max_vel: f32,
+    pub sleep_max_angular_vel: f32,
+    pub sleep_time: f32,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        Self {
+            timestep: physics::TIMESTEP,
+            max_steps_per_frame: physics::MAX_STEPS_PER_FRAME,
+            global_max_vel: physics::GLOBAL_MAX_VEL,
+            global_max_angular_vel: physics::GLOBAL_MAX_ANGULAR_VEL,
+            bounciness: physics::BOUNCINESS,
+            surface_friction: physics::SURFACE_FRICTION,
+            velocity_iterations: physics::SOLVER_VELOCITY_ITERATIONS,
+            position_iterations: physics::SOLVER_POSITION_ITERATIONS,
+            penetration_slop: physics::PENETRATION_SLOP,
+            position_correction: physics::POSITION_CORRECTION,
+            restitution_min_speed: physics::RESTITUTION_MIN_SPEED,
+            sleep_max_vel: physics::SLEEP_MAX_VEL,
+            sleep_max_angular_vel: physics::SLEEP_MAX_ANGULAR_VEL,
+            sleep_time: physics::SLEEP_TIME,
+        }
+    }
+}
+
+impl PhysicsConfig {
+    /// Material of colliders without a [PhysicsMaterial]
+    pub fn default_material(&self) -> PhysicsMaterial {
+        PhysicsMaterial::new(self.bounciness, self.surface_friction)
+    }
+}
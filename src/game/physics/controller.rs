@@ -0,0 +1,131 @@
+//! Controller modes define how a dynamic object moves into solid static
+//! colliders: it can bounce off them, or move up to them and slide along
+//! their surfaces, which is more precise for characters.
+use bevy::math::Vec2;
+use bevy::prelude::*;
+
+use crate::config::physics;
+use crate::util::TransformExt;
+
+use super::{query, Collider};
+
+/// How a [DynamicObject](super::DynamicObject) responds to hitting
+/// solid static colliders
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "debug", derive(bevy_inspector_egui::Inspectable))]
+pub enum ControllerMode {
+    /// Bounces off according to the materials of the colliders
+    Bounce,
+    /// Loses only the velocity into the surface and slides along it.
+    /// Stops `skin_width` away from the surface, and changes the direction
+    /// of the motion at most `max_iterations` times per step
+    Slide {
+        skin_width: f32,
+        max_iterations: u32,
+    },
+}
+
+impl ControllerMode {
+    /// Slide mode with the default skin width and number of iterations
+    pub fn slide() -> Self {
+        Self::Slide {
+            skin_width: physics::SLIDE_SKIN_WIDTH,
+            max_iterations: physics::SLIDE_MAX_ITERATIONS,
+        }
+    }
+}
+
+/// Moves `transform` of the object with `collider` by `vel` * `delta`,
+/// stopping `skin_width` before any of `solids` and sliding along it
+/// with the rest of the motion. The velocity into the hit surfaces
+/// is removed from `vel`
+pub(super) fn move_and_slide(
+    collider: &Collider,
+    transform: &mut Transform,
+    vel: &mut Vec2,
+    delta: f32,
+    (skin_width, max_iterations): (f32, u32),
+    solids: &[&Collider],
+) {
+    const EPS: f32 = 1e-6;
+    let mut cast = collider.clone();
+
+    let mut motion = *vel * delta;
+    for _ in 0..max_iterations {
+        let distance = motion.length();
+        if distance < EPS {
+            return;
+        }
+        let dir = motion / distance;
+        let hit = solids
+            .iter()
+            .filter_map(|other| {
+                query::cast_collider(&mut cast, transform, dir, distance, other)
+            })
+            // Surfaces, that are touched already, don't block moving away
+            .filter(|(_, normal, _)| normal.dot(dir) < 0.0)
+            .min_by(|(distance1, ..), (distance2, ..)| {
+                distance1.partial_cmp(distance2).unwrap()
+            });
+        let (hit_distance, normal, _) = match hit {
+            Some(hit) => hit,
+            None => {
+                transform.translate(motion);
+                return;
+            }
+        };
+
+        let moved = (hit_distance - skin_width).max(0.0);
+        transform.translate(dir * moved);
+        // Slide along the surface with the rest of the motion
+        motion = without_component_into(dir * (distance - moved), normal);
+        *vel = without_component_into(*vel, normal);
+    }
+}
+
+/// Returns `vec` without its component against `normal`
+fn without_component_into(vec: Vec2, normal: Vec2) -> Vec2 {
+    let into = vec.dot(normal);
+    if into < 0.0 {
+        vec - normal * into
+    } else {
+        vec
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::physics::test_util::square;
+
+    #[test]
+    fn move_and_slide_test() {
+        // A wall to the right, the object moves diagonally into it
+        let mut wall =
+            Collider::solid_from_shapes(vec![square(Vec2::ZERO, 1.0)]);
+        wall.update(&Transform::from_xyz(3.0, 0.0, 0.0));
+        let mut collider =
+            Collider::solid_from_shapes(vec![square(Vec2::ZERO, 1.0)]);
+        let mut transform = Transform::identity();
+        collider.update(&transform);
+        let mut vel = Vec2::new(4.0, 2.0);
+        move_and_slide(
+            &collider,
+            &mut transform,
+            &mut vel,
+            1.0,
+            (0.1, 4),
+            &[&wall],
+        );
+
+        // Stopped before the wall and slid along it
+        let translation = transform.translation.truncate();
+        assert!(
+            translation.x > 0.85 && translation.x < 1.0,
+            "{}",
+            translation
+        );
+        assert!((translation.y - 2.0).abs() < 1e-4, "{}", translation);
+        assert!(vel.abs_diff_eq(Vec2::new(0.0, 2.0), 1e-4), "{}", vel);
+    }
+}
@@ -1,7 +1,7 @@
 use bevy::math::Vec2;
 use bevy::prelude::*;
 
-use crate::config::physics;
+use super::{ControllerMode, PhysicsConfig};
 
 /// Component, indicating that this entity can move and collide with colliders
 #[derive(Component, Clone, Debug)]
@@ -10,24 +10,85 @@ pub struct DynamicObject {
     /// External acceleration, is not modified by the physics system
     pub accel: Vec2,
 
+    /// External angular acceleration (in radians/s^2, counter-clockwise),
+    /// is not modified by the physics system
+    pub angular_accel: f32,
+
+    /// Rotation speed (in radians/s, counter-clockwise), driven by the object
+    /// itself, e.g. the player's rotation mechanic. Unlike `angular_vel`,
+    /// it's not affected by friction or collisions.
+    /// Is not modified by the physics system
+    pub spin: f32,
+
+    /// Force, applied with [DynamicObject::apply_force] during this frame
+    pub(super) force: Vec2,
+
     /// Velocity
     pub(super) vel: Vec2,
 
-    /// The square of the speed that this object can not exceed
+    /// Angular velocity (in radians/s, counter-clockwise)
+    pub(super) angular_vel: f32,
+
+    /// Mass of the object
+    pub(super) mass: f32,
+
+    /// Moment of inertia about the object's origin.
+    /// Computed from the collider's shapes and `mass` in `physics::update()`
+    pub(super) inertia: f32,
+
+    /// The square of the speed that this object can not exceed,
+    /// in addition to [PhysicsConfig::global_max_vel](super::PhysicsConfig)
     pub(super) max_vel_squared: f32,
 
     /// Friction_accel = -`vel` * `friction_coeff`
     pub(super) friction_coeff: f32,
+
+    /// Drag_accel = -`vel` * |`vel`| * `drag_coeff`
+    pub(super) drag_coeff: f32,
+
+    /// Angular_friction_accel = -`angular_vel` * `angular_friction_coeff`
+    pub(super) angular_friction_coeff: f32,
+
+    /// How the object responds to hitting solid static colliders
+    pub(super) controller: ControllerMode,
+
+    /// Sleeping objects aren't moved and don't collide with static
+    /// or other sleeping objects, until they are woken up
+    pub(super) sleeping: bool,
+
+    /// Time (in seconds), for which the object has been resting
+    pub(super) rest_time: f32,
+
+    /// Whether the object falls asleep after resting for a while
+    pub(super) can_sleep: bool,
+}
+
+impl Default for DynamicObject {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DynamicObject {
-    /// Returns a dynamic object with global maximum velocity.
+    /// Returns a dynamic object, limited only by the global maximum velocity.
     pub fn new() -> Self {
         Self {
             accel: Vec2::ZERO,
+            angular_accel: 0.0,
+            spin: 0.0,
+            force: Vec2::ZERO,
             vel: Vec2::ZERO,
-            max_vel_squared: physics::GLOBAL_MAX_VEL * physics::GLOBAL_MAX_VEL,
+            angular_vel: 0.0,
+            mass: 1.0,
+            inertia: 0.0,
+            max_vel_squared: f32::INFINITY,
             friction_coeff: 0.0,
+            drag_coeff: 0.0,
+            angular_friction_coeff: 0.0,
+            controller: ControllerMode::Bounce,
+            sleeping: false,
+            rest_time: 0.0,
+            can_sleep: true,
         }
     }
 
@@ -35,14 +96,233 @@ impl DynamicObject {
         max_vel: f32,
         friction_coeff: f32,
     ) -> Self {
+        let mut object = Self::new();
+        object.set_max_vel(max_vel);
+        object.set_friction_coeff(friction_coeff);
+        object
+    }
+
+    /// Returns the object with `mass` set. `mass` must be positive
+    pub fn with_mass(mut self, mass: f32) -> Self {
+        assert!(mass > 0.0, "Non-positive mass = {}", mass);
+        self.mass = mass;
+        self
+    }
+
+    /// Returns the object with `drag_coeff` set, see [Self::set_drag_coeff]
+    pub fn with_drag_coeff(mut self, drag_coeff: f32) -> Self {
+        self.set_drag_coeff(drag_coeff);
+        self
+    }
+
+    /// Returns the object with `angular_friction_coeff` set,
+    /// see [Self::set_angular_friction_coeff]
+    pub fn with_angular_friction_coeff(
+        mut self,
+        angular_friction_coeff: f32,
+    ) -> Self {
+        self.set_angular_friction_coeff(angular_friction_coeff);
+        self
+    }
+
+    /// Returns the object with `controller` set
+    pub fn with_controller(mut self, controller: ControllerMode) -> Self {
+        self.controller = controller;
+        self
+    }
+
+    /// Returns the object with `can_sleep` set
+    pub fn with_can_sleep(mut self, can_sleep: bool) -> Self {
+        self.can_sleep = can_sleep;
+        self
+    }
+
+    pub fn velocity(&self) -> Vec2 {
+        self.vel
+    }
+
+    /// Sets the velocity, e.g. for a dash, and wakes the object up.
+    /// The velocity is clamped to the maximum one by the next physics step
+    pub fn set_velocity(&mut self, vel: Vec2) {
+        self.vel = vel;
+        self.wake_up();
+    }
+
+    /// Instantly changes the velocity by `impulse` / mass, e.g. for
+    /// knockback, and wakes the object up
+    pub fn apply_impulse(&mut self, impulse: Vec2) {
+        self.vel += impulse / self.mass;
+        self.wake_up();
+    }
+
+    /// Applies `force` during every physics step of this frame,
+    /// so it has to be applied each frame for a continuous force.
+    /// Unlike `accel`, the effect of the force depends on the mass
+    pub fn apply_force(&mut self, force: Vec2) {
+        self.force += force;
+    }
+
+    pub fn max_vel(&self) -> f32 {
+        self.max_vel_squared.sqrt()
+    }
+
+    /// Sets the speed, that the object can not exceed
+    pub fn set_max_vel(&mut self, max_vel: f32) {
+        assert!(max_vel >= 0.0, "Negative max_vel = {}", max_vel);
+        self.max_vel_squared = max_vel * max_vel;
+    }
+
+    pub fn friction_coeff(&self) -> f32 {
+        self.friction_coeff
+    }
+
+    /// Sets the linear damping: friction_accel = -`vel` * `friction_coeff`
+    pub fn set_friction_coeff(&mut self, friction_coeff: f32) {
         assert!(
-            max_vel <= physics::GLOBAL_MAX_VEL,
-            "`max_vel` is greater than `GLOBAL_MAX_VEL`"
+            friction_coeff >= 0.0,
+            "Negative friction_coeff = {}",
+            friction_coeff
         );
-        Self {
-            max_vel_squared: max_vel * max_vel,
-            friction_coeff,
-            ..Self::new()
+        self.friction_coeff = friction_coeff;
+    }
+
+    pub fn angular_friction_coeff(&self) -> f32 {
+        self.angular_friction_coeff
+    }
+
+    /// Sets the angular damping:
+    /// angular_friction_accel = -`angular_vel` * `angular_friction_coeff`
+    pub fn set_angular_friction_coeff(&mut self, angular_friction_coeff: f32) {
+        assert!(
+            angular_friction_coeff >= 0.0,
+            "Negative angular_friction_coeff = {}",
+            angular_friction_coeff
+        );
+        self.angular_friction_coeff = angular_friction_coeff;
+    }
+
+    pub fn drag_coeff(&self) -> f32 {
+        self.drag_coeff
+    }
+
+    /// Sets the quadratic drag, that grows with the square of the speed:
+    /// drag_accel = -`vel` * |`vel`| * `drag_coeff`.
+    /// Unlike friction, it's not affected by surfaces
+    pub fn set_drag_coeff(&mut self, drag_coeff: f32) {
+        assert!(drag_coeff >= 0.0, "Negative drag_coeff = {}", drag_coeff);
+        self.drag_coeff = drag_coeff;
+    }
+
+    pub fn is_sleeping(&self) -> bool {
+        self.sleeping
+    }
+
+    /// Wakes the object up, so that it's moved by the next physics step.
+    /// Objects are also woken up by forces, carrying kinematic bodies and
+    /// contacts with moving objects
+    pub fn wake_up(&mut self) {
+        self.sleeping = false;
+        self.rest_time = 0.0;
+    }
+
+    /// Puts the object to sleep, stopping it
+    pub(super) fn fall_asleep(&mut self) {
+        self.sleeping = true;
+        self.vel = Vec2::ZERO;
+        self.angular_vel = 0.0;
+    }
+
+    /// Whether the object is nearly still and is driven neither by itself
+    /// nor by force fields, that accelerate it by `field_accel`
+    pub(super) fn is_resting(
+        &self,
+        field_accel: Vec2,
+        config: &PhysicsConfig,
+    ) -> bool {
+        self.vel.length_squared() < config.sleep_max_vel * config.sleep_max_vel
+            && self.angular_vel.abs() < config.sleep_max_angular_vel
+            && self.accel == Vec2::ZERO
+            && field_accel == Vec2::ZERO
+            && self.force == Vec2::ZERO
+            && self.angular_accel == 0.0
+            && self.spin == 0.0
+    }
+
+    /// Acceleration from the applied force
+    pub(super) fn force_accel(&self) -> Vec2 {
+        self.force / self.mass
+    }
+
+    /// Slows the object down by the drag over `delta` time.
+    /// The drag is integrated exactly, as an explicit step of it would
+    /// reverse the velocity at high speeds
+    pub(super) fn apply_drag(&mut self, delta: f32) {
+        self.vel /= 1.0 + self.vel.length() * self.drag_coeff * delta;
+    }
+
+    /// Velocity of the point of the object, which is at `offset` from its
+    /// origin, taking rotation (including `spin`) into account
+    #[inline]
+    pub(super) fn point_vel(&self, offset: Vec2) -> Vec2 {
+        self.vel + (self.angular_vel + self.spin) * offset.perp()
+    }
+
+    /// Inverse of the mass, that the object has when pushed at `offset`
+    /// from its origin along the normalized `dir`
+    #[inline]
+    pub(super) fn eff_inv_mass(&self, offset: Vec2, dir: Vec2) -> f32 {
+        let offset_cross_dir = offset.perp_dot(dir);
+        1.0 / self.mass
+            + offset_cross_dir * offset_cross_dir * self.inv_inertia()
+    }
+
+    /// Apply `impulse` at `offset` from the object's origin, changing both
+    /// its velocity and angular velocity
+    #[inline]
+    pub(super) fn apply_impulse_at(&mut self, impulse: Vec2, offset: Vec2) {
+        self.vel += impulse / self.mass;
+        self.angular_vel += offset.perp_dot(impulse) * self.inv_inertia();
+    }
+
+    /// Inverse of `inertia`, or 0 if the object can't be rotated by collisions
+    #[inline]
+    pub(super) fn inv_inertia(&self) -> f32 {
+        const EPS: f32 = 1e-6;
+        if self.inertia > EPS {
+            1.0 / self.inertia
+        } else {
+            0.0
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPS: f32 = 1e-4;
+
+    #[test]
+    fn impulse_test() {
+        let mut object = DynamicObject::new().with_mass(2.0);
+        object.fall_asleep();
+        object.apply_impulse(Vec2::new(4.0, 0.0));
+        assert!(!object.is_sleeping());
+        assert!(object.velocity().abs_diff_eq(Vec2::new(2.0, 0.0), EPS));
+
+        // Force divided by the mass
+        object.apply_force(Vec2::new(0.0, 6.0));
+        let accel = object.force_accel();
+        assert!(accel.abs_diff_eq(Vec2::new(0.0, 3.0), EPS), "{}", accel);
+
+        // Drag slows the object down, but never reverses it
+        object.set_drag_coeff(0.5);
+        object.apply_drag(1.0);
+        let vel = object.velocity();
+        assert!(vel.abs_diff_eq(Vec2::new(1.0, 0.0), EPS), "{}", vel);
+        object.set_velocity(Vec2::new(1000.0, 0.0));
+        object.apply_drag(1.0);
+        let vel = object.velocity();
+        assert!(vel.x > 0.0 && vel.x < 2.0, "{}", vel);
+    }
+}
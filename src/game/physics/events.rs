@@ -0,0 +1,82 @@
+//! Events about pairs of colliders starting, continuing and ending to collide.
+//! They are derived from the recent collisions of all colliders by comparing
+//! them with the ones of the previous frame.
+use std::collections::BTreeMap;
+
+use bevy::math::Vec2;
+use bevy::prelude::*;
+
+use super::Collider;
+
+/// Data of a collision between two colliders
+#[derive(Copy, Clone, Debug)]
+pub struct CollisionData {
+    /// Colliding entities, `entities.0 < entities.1`
+    pub entities: (Entity, Entity),
+    /// Minimum Push Vector to push `entities.1` out of `entities.0`
+    pub mpv: Vec2,
+    /// Approximate world-space point of contact
+    pub contact: Vec2,
+    /// Speed, with which the colliders were approaching each other
+    pub impact_speed: f32,
+}
+
+/// Event, sent once when two colliders start colliding
+#[derive(Copy, Clone, Debug)]
+pub struct CollisionStarted(pub CollisionData);
+
+/// Event, sent every frame (except the first one) while two colliders collide
+#[derive(Copy, Clone, Debug)]
+pub struct CollisionOngoing(pub CollisionData);
+
+/// Event, sent once when two colliders stop colliding (or one of them is
+/// despawned). Contains the data of the last frame they collided
+#[derive(Copy, Clone, Debug)]
+pub struct CollisionEnded(pub CollisionData);
+
+/// Colliding pairs of entities
+type Pairs = BTreeMap<(Entity, Entity), CollisionData>;
+
+/// System, that sends collision events. Must run after `physics::update`
+pub fn send_collision_events(
+    mut prev_pairs: Local<Pairs>,
+    collider_query: Query<(Entity, &Collider)>,
+    mut started_writer: EventWriter<CollisionStarted>,
+    mut ongoing_writer: EventWriter<CollisionOngoing>,
+    mut ended_writer: EventWriter<CollisionEnded>,
+) {
+    // Every collision is stored in both colliders,
+    // so only take it from the one with the smaller entity
+    let mut pairs = Pairs::new();
+    for (entity, collider) in collider_query.iter() {
+        for collision in collider.get_recent_collisions() {
+            if entity < collision.other_entity {
+                let entities = (entity, collision.other_entity);
+                pairs.insert(
+                    entities,
+                    CollisionData {
+                        entities,
+                        mpv: collision.mpv,
+                        contact: collision.contact,
+                        impact_speed: collision.impact_speed,
+                    },
+                );
+            }
+        }
+    }
+
+    for (entities, data) in &pairs {
+        if prev_pairs.contains_key(entities) {
+            ongoing_writer.send(CollisionOngoing(*data));
+        } else {
+            started_writer.send(CollisionStarted(*data));
+        }
+    }
+    for (entities, data) in prev_pairs.iter() {
+        if !pairs.contains_key(entities) {
+            ended_writer.send(CollisionEnded(*data));
+        }
+    }
+
+    *prev_pairs = pairs;
+}
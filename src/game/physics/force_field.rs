@@ -0,0 +1,99 @@
+//! Force fields are nonsolid colliders, which accelerate every dynamic object,
+//! whose origin is inside of them. Which objects are affected is controlled
+//! with the collider's `layers`.
+use bevy::math::Vec2;
+use bevy::prelude::*;
+
+use super::Collider;
+
+/// Component, indicating that this entity's (nonsolid) collider
+/// is a force field
+#[derive(Component, Copy, Clone, Debug)]
+#[cfg_attr(feature = "debug", derive(bevy_inspector_egui::Inspectable))]
+pub enum ForceField {
+    /// Constant acceleration, e.g. a gravity zone or wind
+    Uniform { accel: Vec2 },
+    /// Acceleration towards the center of the field,
+    /// or away from it if `strength` is negative
+    Radial { strength: f32 },
+    /// Acceleration around the center of the field, counter-clockwise,
+    /// or clockwise if `strength` is negative
+    Vortex { strength: f32 },
+    /// Drags objects towards the velocity `vel` with acceleration
+    /// (`vel` - object's velocity) * `coeff`
+    Current { vel: Vec2, coeff: f32 },
+}
+
+impl Default for ForceField {
+    fn default() -> Self {
+        Self::Uniform { accel: Vec2::ZERO }
+    }
+}
+
+impl ForceField {
+    /// Acceleration of an object at `point` with velocity `vel`,
+    /// if the center of the field is `center`
+    pub(super) fn accel(&self, point: Vec2, vel: Vec2, center: Vec2) -> Vec2 {
+        match *self {
+            ForceField::Uniform { accel } => accel,
+            ForceField::Radial { strength } => {
+                (center - point).normalize_or_zero() * strength
+            }
+            ForceField::Vortex { strength } => {
+                (point - center).normalize_or_zero().perp() * strength
+            }
+            ForceField::Current {
+                vel: current_vel,
+                coeff,
+            } => (current_vel - vel) * coeff,
+        }
+    }
+}
+
+/// Total acceleration of a dynamic object with `collider` at `point` with
+/// velocity `vel` from all `fields`, that contain `point`
+pub(super) fn total_accel(
+    fields: &[(&ForceField, &Collider)],
+    collider: &Collider,
+    point: Vec2,
+    vel: Vec2,
+) -> Vec2 {
+    fields
+        .iter()
+        .filter(|(_, field_collider)| {
+            field_collider.layers.interacts_with(&collider.layers)
+                && field_collider.contains_point(point)
+        })
+        .fold(Vec2::ZERO, |accel, (field, field_collider)| {
+            // A collider, that contains the point, has a bounding box
+            let center = field_collider.bounding_box().unwrap().center();
+            accel + field.accel(point, vel, center)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPS: f32 = 1e-5;
+
+    #[test]
+    fn accel_test() {
+        let (point, center) = (Vec2::new(2.0, 0.0), Vec2::ZERO);
+        let radial = ForceField::Radial { strength: 3.0 };
+        assert!(radial
+            .accel(point, Vec2::ZERO, center)
+            .abs_diff_eq(Vec2::new(-3.0, 0.0), EPS));
+        let vortex = ForceField::Vortex { strength: 3.0 };
+        assert!(vortex
+            .accel(point, Vec2::ZERO, center)
+            .abs_diff_eq(Vec2::new(0.0, 3.0), EPS));
+        let current = ForceField::Current {
+            vel: Vec2::new(10.0, 0.0),
+            coeff: 0.5,
+        };
+        assert!(current
+            .accel(point, Vec2::new(10.0, 2.0), center)
+            .abs_diff_eq(Vec2::new(0.0, -1.0), EPS));
+    }
+}
@@ -0,0 +1,350 @@
+//! GJK and EPA algorithms: distance, closest points and penetration depth
+//! between any two convex [Shape]s, only using their support points.
+//!
+//! GJK looks for the point of the Minkowski difference `shape1 - shape2`,
+//! that is closest to the origin. If the origin is inside of the difference,
+//! the shapes overlap and EPA expands a polytope inside of the difference
+//! to find the closest point on its boundary instead.
+use bevy::math::Vec2;
+
+use crate::util::Vec2Ext;
+
+use super::shape::Shape;
+
+/// Closest points between two shapes
+#[derive(Copy, Clone, Debug)]
+pub struct ClosestPoints {
+    /// Point of the first shape, that is closest to the second shape
+    /// (or deepest inside of it, if they overlap)
+    pub point1: Vec2,
+    /// Point of the second shape, that is closest to the first shape
+    /// (or deepest inside of it, if they overlap)
+    pub point2: Vec2,
+    /// Distance between the shapes, or negative penetration depth,
+    /// if they overlap
+    pub signed_distance: f32,
+    /// Normalized direction, along which the second shape has to be moved
+    /// to get further away from the first one
+    pub normal: Vec2,
+}
+
+/// Distance between two shapes, 0 if they overlap
+pub fn distance(shape1: &Shape, shape2: &Shape) -> f32 {
+    closest_points(shape1, shape2).signed_distance.max(0.0)
+}
+
+/// Distance between two shapes, or negative penetration depth,
+/// if they overlap
+pub fn signed_distance(shape1: &Shape, shape2: &Shape) -> f32 {
+    closest_points(shape1, shape2).signed_distance
+}
+
+/// Closest points between two shapes
+pub fn closest_points(shape1: &Shape, shape2: &Shape) -> ClosestPoints {
+    match gjk(shape1, shape2) {
+        Gjk::Separated(closest) => {
+            let distance = closest.point.length();
+            ClosestPoints {
+                point1: closest.point1,
+                point2: closest.point2,
+                signed_distance: distance,
+                normal: -closest.point / distance,
+            }
+        }
+        Gjk::Touching(closest, dir) => ClosestPoints {
+            point1: closest.point1,
+            point2: closest.point2,
+            signed_distance: 0.0,
+            normal: dir.normalize_or_zero(),
+        },
+        Gjk::Overlapping(triangle) => epa(shape1, shape2, triangle),
+    }
+}
+
+const MAX_ITERATIONS: usize = 64;
+/// Tolerance of the distance
+const EPS: f32 = 1e-4;
+
+/// Point of the Minkowski difference with the points of the shapes,
+/// that it is the difference of
+#[derive(Copy, Clone, Debug)]
+struct Vertex {
+    point: Vec2,
+    point1: Vec2,
+    point2: Vec2,
+}
+
+impl Vertex {
+    /// Support point of the Minkowski difference along `dir`
+    fn support(shape1: &Shape, shape2: &Shape, dir: Vec2) -> Self {
+        let point1 = shape1.support(dir);
+        let point2 = shape2.support(-dir);
+        Self {
+            point: point1 - point2,
+            point1,
+            point2,
+        }
+    }
+
+    /// `self` * (1 - `t`) + `other` * `t`
+    fn lerp(&self, other: &Vertex, t: f32) -> Self {
+        Self {
+            point: self.point.lerp(other.point, t),
+            point1: self.point1.lerp(other.point1, t),
+            point2: self.point2.lerp(other.point2, t),
+        }
+    }
+}
+
+enum Gjk {
+    /// The closest point of the difference to the origin
+    Separated(Vertex),
+    /// The origin is on the boundary of the difference.
+    /// Contains the closest point and the last search direction
+    Touching(Vertex, Vec2),
+    /// Triangle of the difference, that contains the origin
+    Overlapping([Vertex; 3]),
+}
+
+fn gjk(shape1: &Shape, shape2: &Shape) -> Gjk {
+    let mut dir = Vec2::X;
+    let mut simplex = vec![Vertex::support(shape1, shape2, dir)];
+    let mut closest = simplex[0];
+    for _ in 0..MAX_ITERATIONS {
+        closest = match reduce_simplex(&mut simplex) {
+            Some(closest) => closest,
+            None => {
+                return Gjk::Overlapping([simplex[0], simplex[1], simplex[2]])
+            }
+        };
+        let distance = closest.point.length();
+        if distance < EPS {
+            return expand_segment(shape1, shape2, &simplex, closest)
+                .unwrap_or(Gjk::Touching(closest, dir));
+        }
+
+        dir = -closest.point;
+        let vertex = Vertex::support(shape1, shape2, dir);
+        // No more progress towards the origin
+        if distance * distance - closest.point.dot(vertex.point)
+            <= EPS * distance
+        {
+            break;
+        }
+        simplex.push(vertex);
+    }
+    Gjk::Separated(closest)
+}
+
+/// If the origin is on the segment `simplex` (e.g. when the shapes have
+/// parallel edges), the difference around it is searched on both sides of the
+/// segment. Returns `None` if the simplex isn't a segment
+fn expand_segment(
+    shape1: &Shape,
+    shape2: &Shape,
+    simplex: &[Vertex],
+    closest: Vertex,
+) -> Option<Gjk> {
+    let (a, b) = match simplex {
+        [a, b] => (*a, *b),
+        _ => return None,
+    };
+    let side = (b.point - a.point).perp().normalize_or_zero();
+    let mut vertices = [side, -side].into_iter().map(|dir| {
+        let vertex = Vertex::support(shape1, shape2, dir);
+        if vertex.point.dot(dir) > EPS {
+            Ok(vertex)
+        } else {
+            Err(dir)
+        }
+    });
+    // The segment is on the boundary of the difference,
+    // if there's nothing of it on one of the sides
+    match (vertices.next()?, vertices.next()?) {
+        (Ok(vertex), Ok(_)) => Some(Gjk::Overlapping([a, b, vertex])),
+        (Err(dir), _) | (_, Err(dir)) => Some(Gjk::Touching(closest, dir)),
+    }
+}
+
+/// Returns the point of the segment, that is closest to the origin, and
+/// reduces the segment to the vertices, needed to represent it
+fn reduce_segment(a: Vertex, b: Vertex, simplex: &mut Vec<Vertex>) -> Vertex {
+    let edge = b.point - a.point;
+    let t = if edge.length_squared() > 0.0 {
+        -a.point.dot(edge) / edge.length_squared()
+    } else {
+        0.0
+    };
+    simplex.clear();
+    if t <= 0.0 {
+        simplex.push(a);
+        a
+    } else if t >= 1.0 {
+        simplex.push(b);
+        b
+    } else {
+        simplex.extend_from_slice(&[a, b]);
+        a.lerp(&b, t)
+    }
+}
+
+/// Returns the point of the simplex, that is closest to the origin, and
+/// reduces the simplex to the vertices, needed to represent it.
+/// Returns `None` if the simplex is a triangle, containing the origin
+fn reduce_simplex(simplex: &mut Vec<Vertex>) -> Option<Vertex> {
+    match simplex[..] {
+        [a] => Some(a),
+        [a, b] => Some(reduce_segment(a, b, simplex)),
+        [a, b, c] => {
+            // The origin is inside if it's on the same side of all edges
+            let area = (b.point - a.point).perp_dot(c.point - a.point);
+            let inside = [(a, b), (b, c), (c, a)].iter().all(|(from, to)| {
+                (to.point - from.point).perp_dot(-from.point) * area >= 0.0
+            });
+            if inside && area != 0.0 {
+                return None;
+            }
+            // Otherwise the closest point is on one of the edges
+            let (closest, edge_simplex) = [(a, b), (b, c), (c, a)]
+                .iter()
+                .map(|&(from, to)| {
+                    let mut edge_simplex = Vec::with_capacity(2);
+                    let point = reduce_segment(from, to, &mut edge_simplex);
+                    (point, edge_simplex)
+                })
+                .min_by(|(point1, _), (point2, _)| {
+                    point1
+                        .point
+                        .length_squared()
+                        .partial_cmp(&point2.point.length_squared())
+                        .unwrap()
+                })
+                .unwrap();
+            *simplex = edge_simplex;
+            Some(closest)
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Expands `triangle`, which contains the origin, inside of the difference
+/// until the edge, closest to the origin, is on the boundary of it
+fn epa(shape1: &Shape, shape2: &Shape, triangle: [Vertex; 3]) -> ClosestPoints {
+    let mut polytope = triangle.to_vec();
+    // Keep the polytope counter-clockwise
+    if (polytope[1].point - polytope[0].point)
+        .perp_dot(polytope[2].point - polytope[0].point)
+        < 0.0
+    {
+        polytope.swap(1, 2);
+    }
+
+    let mut closest_edge = (0, Vec2::ZERO, f32::INFINITY);
+    for _ in 0..MAX_ITERATIONS {
+        // Find the edge, closest to the origin
+        closest_edge = (0, Vec2::ZERO, f32::INFINITY);
+        for index in 0..polytope.len() {
+            let from = polytope[index].point;
+            let to = polytope[(index + 1) % polytope.len()].point;
+            let normal = (to - from).rotate_clockwise_90().normalize_or_zero();
+            let distance = normal.dot(from);
+            if normal != Vec2::ZERO && distance < closest_edge.2 {
+                closest_edge = (index, normal, distance);
+            }
+        }
+
+        // Stop if it can't be expanded any further
+        let (index, normal, distance) = closest_edge;
+        let vertex = Vertex::support(shape1, shape2, normal);
+        if vertex.point.dot(normal) - distance <= EPS {
+            break;
+        }
+        polytope.insert(index + 1, vertex);
+    }
+
+    // The origin projected onto the closest edge
+    let (index, normal, distance) = closest_edge;
+    let from = polytope[index];
+    let to = polytope[(index + 1) % polytope.len()];
+    let mut edge_simplex = Vec::with_capacity(2);
+    let closest = reduce_segment(from, to, &mut edge_simplex);
+    ClosestPoints {
+        point1: closest.point1,
+        point2: closest.point2,
+        signed_distance: -distance,
+        normal,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::transform::components::Transform;
+
+    use super::super::shape::{CircleShape, ShiftedShape};
+    use super::super::test_util::square;
+    use super::*;
+
+    const EPS: f32 = 1e-3;
+
+    fn circle(center: Vec2, radius: f32) -> Shape {
+        Shape::Circle(CircleShape::new(radius, center))
+    }
+
+    #[test]
+    fn distance_test() {
+        let square1 = square(Vec2::ZERO, 1.0);
+        let square2 = square(Vec2::new(3.0, 0.5), 1.0);
+        let closest = closest_points(&square1, &square2);
+        assert!((closest.signed_distance - 1.0).abs() < EPS);
+        assert!(closest.normal.abs_diff_eq(Vec2::X, EPS));
+        assert!((closest.point1.x - 1.0).abs() < EPS);
+        assert!((closest.point2.x - 2.0).abs() < EPS);
+
+        // Corner to corner
+        let square3 = square(Vec2::new(3.0, 3.0), 1.0);
+        assert!((distance(&square1, &square3) - 2.0_f32.sqrt()).abs() < EPS);
+
+        let circle1 = circle(Vec2::new(0.0, 4.0), 1.0);
+        assert!((distance(&square1, &circle1) - 2.0).abs() < EPS);
+        let circle2 = circle(Vec2::new(4.0, 4.0), 2.0);
+        assert!((distance(&circle1, &circle2) - 1.0).abs() < EPS);
+    }
+
+    /// The penetration, found by EPA, must match the mpv, found by SAT
+    #[test]
+    fn penetration_matches_sat_test() {
+        let pairs = [
+            (square(Vec2::ZERO, 1.0), square(Vec2::new(1.5, 0.2), 1.0)),
+            (square(Vec2::ZERO, 1.0), square(Vec2::new(-0.3, -1.8), 1.0)),
+            (square(Vec2::ZERO, 1.0), circle(Vec2::new(0.4, 1.5), 1.0)),
+            (circle(Vec2::ZERO, 1.0), circle(Vec2::new(1.0, 1.0), 1.0)),
+        ];
+        let mut normal_buf = Vec::new();
+        for (shape1, shape2) in pairs {
+            let mut shifted1 =
+                ShiftedShape::from_original_shape(shape1.clone());
+            let mut shifted2 =
+                ShiftedShape::from_original_shape(shape2.clone());
+            shifted1.update(&Transform::identity());
+            shifted2.update(&Transform::identity());
+            let mpv = shifted1
+                .process_collision(&shifted2, &mut normal_buf)
+                .unwrap();
+
+            let closest = closest_points(&shape1, &shape2);
+            assert!(
+                (closest.signed_distance + mpv.length()).abs() < EPS,
+                "{:?} vs {:?}",
+                closest,
+                mpv
+            );
+            // The normal converges slower than the depth on curved shapes
+            assert!(
+                closest.normal.abs_diff_eq(mpv.normalize(), 1e-2),
+                "{:?} vs {:?}",
+                closest,
+                mpv
+            );
+        }
+    }
+}
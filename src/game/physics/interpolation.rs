@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+/// Component, that smooths the rendered motion of a dynamic object
+/// or a kinematic body. Physics steps have a fixed length, which doesn't
+/// match the frames, so the rendered transform is interpolated between the
+/// transforms after the last two steps.
+/// The transform of the last step is restored before the next physics update,
+/// unless it was changed by something else (e.g. teleported)
+#[derive(Component, Clone, Debug, Default)]
+pub struct Interpolation {
+    /// Transform before the last step
+    prev: Transform,
+    /// Transform after the last step
+    curr: Transform,
+    /// Transform, that was set by the last interpolation
+    rendered: Option<Transform>,
+}
+
+impl Interpolation {
+    /// Returns an interpolation, that starts from the first physics update
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replaces the rendered `transform` with the one of the last step
+    pub(super) fn restore(&mut self, transform: &mut Transform) {
+        if self.rendered == Some(*transform) {
+            *transform = self.curr;
+        } else {
+            // Moved outside of the physics, so don't interpolate the jump
+            self.prev = *transform;
+            self.curr = *transform;
+        }
+    }
+
+    /// Remembers `transform` before the last step of the frame
+    pub(super) fn start_step(&mut self, transform: &Transform) {
+        self.prev = *transform;
+    }
+
+    /// Remembers `transform` after the last step of the frame
+    pub(super) fn end_step(&mut self, transform: &Transform) {
+        self.curr = *transform;
+    }
+
+    /// Sets `transform` to the one between the last two steps.
+    /// `alpha` is the part of the step, that has passed since the last one
+    pub(super) fn interpolate(
+        &mut self,
+        transform: &mut Transform,
+        alpha: f32,
+    ) {
+        *transform = Transform {
+            translation: self
+                .prev
+                .translation
+                .lerp(self.curr.translation, alpha),
+            rotation: self.prev.rotation.slerp(self.curr.rotation, alpha),
+            scale: self.prev.scale.lerp(self.curr.scale, alpha),
+        };
+        self.rendered = Some(*transform);
+    }
+}
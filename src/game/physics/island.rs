@@ -0,0 +1,54 @@
+//! Islands are groups of dynamic objects, connected by contacts or joints.
+//! Objects of an island are woken up and fall asleep together, so that
+//! a sleeping object isn't left inside of a moving one.
+
+/// Disjoint sets of dynamic objects, referred to by their indices
+pub(super) struct Islands {
+    parents: Vec<usize>,
+}
+
+impl Islands {
+    /// `count` objects, each in its own island
+    pub(super) fn new(count: usize) -> Self {
+        Self {
+            parents: (0..count).collect(),
+        }
+    }
+
+    /// Index of the object, that represents the island of `index`
+    pub(super) fn find(&mut self, index: usize) -> usize {
+        let mut root = index;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+        // Shorten the path for the next searches
+        let mut index = index;
+        while self.parents[index] != root {
+            let parent = self.parents[index];
+            self.parents[index] = root;
+            index = parent;
+        }
+        root
+    }
+
+    /// Merges the islands of `index1` and `index2`
+    pub(super) fn union(&mut self, index1: usize, index2: usize) {
+        let (root1, root2) = (self.find(index1), self.find(index2));
+        self.parents[root1.max(root2)] = root1.min(root2);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn islands_test() {
+        let mut islands = Islands::new(5);
+        islands.union(0, 3);
+        islands.union(4, 3);
+        assert_eq!(islands.find(4), islands.find(0));
+        assert_ne!(islands.find(1), islands.find(0));
+        assert_ne!(islands.find(1), islands.find(2));
+    }
+}
@@ -0,0 +1,342 @@
+//! Joints constrain the relative motion of two dynamic objects, or of
+//! a dynamic object and a fixed world point. They are solved together with
+//! the contacts, see [Solver](super::solver::Solver).
+use bevy::math::{Mat2, Vec2};
+use bevy::prelude::*;
+
+use super::DynamicObject;
+
+/// How a [Joint] constrains its anchors
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum JointKind {
+    /// Keeps the anchors at `length` from each other, like a rod
+    Distance { length: f32 },
+    /// Keeps the anchors at most `max_length` from each other
+    Rope { max_length: f32 },
+    /// Pulls the anchors to `rest_length` from each other with force
+    /// `stiffness` * (`rest_length` - distance), damped by `damping`
+    /// times their relative speed
+    Spring {
+        rest_length: f32,
+        stiffness: f32,
+        damping: f32,
+    },
+    /// Keeps the anchors at the same point, letting the objects rotate
+    /// around it
+    Hinge,
+}
+
+/// Component of a separate entity, that joins two objects.
+/// Joints, whose first body isn't a dynamic object, are ignored
+#[derive(Component, Copy, Clone, Debug)]
+pub struct Joint {
+    pub kind: JointKind,
+    /// Dynamic object
+    pub body1: Entity,
+    /// Anchor point in the local space of `body1`
+    pub anchor1: Vec2,
+    /// The other dynamic object, or `None` for a fixed world point
+    pub body2: Option<Entity>,
+    /// Anchor point in the local space of `body2`,
+    /// or the world point if `body2` is `None`
+    pub anchor2: Vec2,
+}
+
+impl Joint {
+    /// Joint between the origins of two dynamic objects
+    pub fn between(kind: JointKind, body1: Entity, body2: Entity) -> Self {
+        Self {
+            kind,
+            body1,
+            anchor1: Vec2::ZERO,
+            body2: Some(body2),
+            anchor2: Vec2::ZERO,
+        }
+    }
+
+    /// Joint between the origin of a dynamic object and a world `point`
+    pub fn to_point(kind: JointKind, body1: Entity, point: Vec2) -> Self {
+        Self {
+            kind,
+            body1,
+            anchor1: Vec2::ZERO,
+            body2: None,
+            anchor2: point,
+        }
+    }
+
+    /// Returns the joint with `anchor1` set
+    pub fn with_anchor1(mut self, anchor1: Vec2) -> Self {
+        self.anchor1 = anchor1;
+        self
+    }
+
+    /// Returns the joint with `anchor2` set
+    pub fn with_anchor2(mut self, anchor2: Vec2) -> Self {
+        self.anchor2 = anchor2;
+        self
+    }
+}
+
+/// Offset of a local `anchor` from the origin of the object with `transform`
+pub(super) fn anchor_offset(transform: &Transform, anchor: Vec2) -> Vec2 {
+    (transform.rotation * (anchor * transform.scale.truncate()).extend(0.0))
+        .truncate()
+}
+
+/// [Joint], prepared for solving.
+/// Like in contacts, the first object is pushed along the axis,
+/// and the second one is pushed the opposite way
+#[derive(Clone, Debug)]
+pub(super) struct JointConstraint {
+    kind: JointKind,
+    body1: usize,
+    body2: Option<usize>,
+    /// Offsets of the anchors from the origins of the objects
+    offsets: (Vec2, Vec2),
+    /// World point of the second anchor, if it's fixed
+    point: Vec2,
+    /// Axis from the second anchor to the first one
+    axis: Vec2,
+    /// Distance between the anchors
+    distance: f32,
+    /// Impulse along the axis, accumulated over the iterations
+    axis_impulse: f32,
+}
+
+impl JointConstraint {
+    /// `offsets` are the offsets of the anchors (see [anchor_offset]),
+    /// `anchors` are their world points
+    pub(super) fn new(
+        kind: JointKind,
+        (body1, body2): (usize, Option<usize>),
+        offsets: (Vec2, Vec2),
+        anchors: (Vec2, Vec2),
+    ) -> Self {
+        let distance = anchors.0.distance(anchors.1);
+        Self {
+            kind,
+            body1,
+            body2,
+            offsets,
+            point: anchors.1,
+            axis: (anchors.0 - anchors.1).normalize_or_zero(),
+            distance,
+            axis_impulse: 0.0,
+        }
+    }
+
+    /// Velocity of the first anchor relative to the second one
+    fn relative_vel(&self, bodies: &[DynamicObject]) -> Vec2 {
+        let vel2 = self.body2.map_or(Vec2::ZERO, |body2| {
+            bodies[body2].point_vel(self.offsets.1)
+        });
+        bodies[self.body1].point_vel(self.offsets.0) - vel2
+    }
+
+    /// Applies `impulse` to the first object and the opposite one to the second
+    fn apply_impulse(&self, bodies: &mut [DynamicObject], impulse: Vec2) {
+        bodies[self.body1].apply_impulse_at(impulse, self.offsets.0);
+        if let Some(body2) = self.body2 {
+            bodies[body2].apply_impulse_at(-impulse, self.offsets.1);
+        }
+    }
+
+    /// Inverse of the effective mass of both objects along `dir`
+    fn inv_mass(&self, bodies: &[DynamicObject], dir: Vec2) -> f32 {
+        bodies[self.body1].eff_inv_mass(self.offsets.0, dir)
+            + self.body2.map_or(0.0, |body2| {
+                bodies[body2].eff_inv_mass(self.offsets.1, dir)
+            })
+    }
+
+    /// Applies the spring force over `delta` seconds. Other kinds of joints
+    /// don't apply any forces before solving
+    pub(super) fn apply_force(&self, bodies: &mut [DynamicObject], delta: f32) {
+        if let JointKind::Spring {
+            rest_length,
+            stiffness,
+            damping,
+        } = self.kind
+        {
+            let axis_vel = self.relative_vel(bodies).dot(self.axis);
+            let force =
+                stiffness * (rest_length - self.distance) - damping * axis_vel;
+            self.apply_impulse(bodies, self.axis * (force * delta));
+        }
+    }
+
+    pub(super) fn solve_velocity(&mut self, bodies: &mut [DynamicObject]) {
+        let relative_vel = self.relative_vel(bodies);
+        match self.kind {
+            JointKind::Distance { .. } | JointKind::Rope { .. } => {
+                let inv_mass = self.inv_mass(bodies, self.axis);
+                if inv_mass == 0.0 {
+                    return;
+                }
+                let delta = -relative_vel.dot(self.axis) / inv_mass;
+                let old_impulse = self.axis_impulse;
+                self.axis_impulse = match self.kind {
+                    // A taut rope can only pull the objects together
+                    JointKind::Rope { max_length } => {
+                        if self.distance < max_length {
+                            return;
+                        }
+                        (old_impulse + delta).min(0.0)
+                    }
+                    _ => old_impulse + delta,
+                };
+                let impulse = self.axis * (self.axis_impulse - old_impulse);
+                self.apply_impulse(bodies, impulse);
+            }
+            JointKind::Spring { .. } => {}
+            JointKind::Hinge => {
+                // Remove the relative velocity of the anchors
+                // in both directions at once
+                let mass_matrix = Mat2::from_cols(
+                    self.column(bodies, Vec2::X),
+                    self.column(bodies, Vec2::Y),
+                );
+                if mass_matrix.determinant().abs() < f32::EPSILON {
+                    return;
+                }
+                let impulse = -(mass_matrix.inverse() * relative_vel);
+                self.apply_impulse(bodies, impulse);
+            }
+        }
+    }
+
+    /// Change of the relative velocity of the anchors
+    /// by a unit `impulse`
+    fn column(&self, bodies: &[DynamicObject], impulse: Vec2) -> Vec2 {
+        let column = |body: &DynamicObject, offset: Vec2| {
+            impulse / body.mass
+                + offset.perp()
+                    * (offset.perp_dot(impulse) * body.inv_inertia())
+        };
+        column(&bodies[self.body1], self.offsets.0)
+            + self.body2.map_or(Vec2::ZERO, |body2| {
+                column(&bodies[body2], self.offsets.1)
+            })
+    }
+
+    /// Pushes the anchors towards the allowed distance in proportion to the
+    /// inverse masses of the objects, with `translations` of the objects
+    /// and `corrections` of them, that are applied after solving.
+    /// `position_correction` is the part of the error, corrected at once
+    pub(super) fn solve_position(
+        &self,
+        bodies: &[DynamicObject],
+        translations: &[Vec2],
+        corrections: &mut [Vec2],
+        position_correction: f32,
+    ) {
+        let anchor1 =
+            translations[self.body1] + corrections[self.body1] + self.offsets.0;
+        let (anchor2, inv_mass2) = match self.body2 {
+            Some(body2) => (
+                translations[body2] + corrections[body2] + self.offsets.1,
+                1.0 / bodies[body2].mass,
+            ),
+            None => (self.point, 0.0),
+        };
+        let inv_mass1 = 1.0 / bodies[self.body1].mass;
+        let diff = anchor1 - anchor2;
+        let distance = diff.length();
+        let error = match self.kind {
+            JointKind::Distance { length } => {
+                diff.normalize_or_zero() * (distance - length)
+            }
+            JointKind::Rope { max_length } => {
+                diff.normalize_or_zero() * (distance - max_length).max(0.0)
+            }
+            JointKind::Spring { .. } => return,
+            JointKind::Hinge => diff,
+        };
+        let push = -error * position_correction / (inv_mass1 + inv_mass2);
+        corrections[self.body1] += push * inv_mass1;
+        if let Some(body2) = self.body2 {
+            corrections[body2] -= push * inv_mass2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPS: f32 = 1e-4;
+
+    fn moving_object(vel: Vec2) -> DynamicObject {
+        let mut object = DynamicObject::new();
+        object.vel = vel;
+        object
+    }
+
+    #[test]
+    fn rope_test() {
+        // A taut rope stops moving away from the point, but not towards it
+        let rope = JointKind::Rope { max_length: 10.0 };
+        let anchors = (Vec2::new(10.0, 0.0), Vec2::ZERO);
+        let mut bodies = [moving_object(Vec2::new(5.0, 3.0))];
+        let mut joint = JointConstraint::new(
+            rope,
+            (0, None),
+            (Vec2::ZERO, Vec2::ZERO),
+            anchors,
+        );
+        joint.solve_velocity(&mut bodies);
+        assert!(bodies[0].vel.abs_diff_eq(Vec2::new(0.0, 3.0), EPS));
+
+        let mut bodies = [moving_object(Vec2::new(-5.0, 0.0))];
+        let mut joint = JointConstraint::new(
+            rope,
+            (0, None),
+            (Vec2::ZERO, Vec2::ZERO),
+            anchors,
+        );
+        joint.solve_velocity(&mut bodies);
+        assert!(bodies[0].vel.abs_diff_eq(Vec2::new(-5.0, 0.0), EPS));
+    }
+
+    #[test]
+    fn hinge_test() {
+        // Two equal objects, pinned together, move as one
+        let mut bodies = [
+            moving_object(Vec2::new(4.0, 0.0)),
+            moving_object(Vec2::new(0.0, 2.0)),
+        ];
+        let offsets = (Vec2::new(1.0, 0.0), Vec2::new(-1.0, 0.0));
+        let mut joint = JointConstraint::new(
+            JointKind::Hinge,
+            (0, Some(1)),
+            offsets,
+            (Vec2::new(1.0, 0.0), Vec2::new(1.0, 0.0)),
+        );
+        joint.solve_velocity(&mut bodies);
+        assert!(bodies[0].vel.abs_diff_eq(Vec2::new(2.0, 1.0), EPS));
+        assert!(bodies[1].vel.abs_diff_eq(Vec2::new(2.0, 1.0), EPS));
+    }
+
+    #[test]
+    fn distance_position_test() {
+        // A stretched rod is pulled back to its length
+        let joint = JointConstraint::new(
+            JointKind::Distance { length: 5.0 },
+            (0, None),
+            (Vec2::ZERO, Vec2::ZERO),
+            (Vec2::new(0.0, 6.0), Vec2::ZERO),
+        );
+        let bodies = [DynamicObject::new()];
+        let mut corrections = [Vec2::ZERO];
+        for _ in 0..20 {
+            joint.solve_position(
+                &bodies,
+                &[Vec2::new(0.0, 6.0)],
+                &mut corrections,
+                0.8,
+            );
+        }
+        assert!(corrections[0].abs_diff_eq(Vec2::new(0.0, -1.0), EPS));
+    }
+}
@@ -0,0 +1,53 @@
+use bevy::math::Vec2;
+use bevy::prelude::*;
+
+/// Component, indicating that this static collider object is moved by its
+/// velocity instead of collisions, e.g. a moving platform or a rotating door.
+/// Its motion is taken into account when bouncing dynamic objects off it.
+/// Must not be on an entity with a [DynamicObject](super::DynamicObject)
+#[derive(Component, Copy, Clone, Debug, Default)]
+#[cfg_attr(feature = "debug", derive(bevy_inspector_egui::Inspectable))]
+pub struct KinematicBody {
+    /// Velocity, is not modified by the physics system,
+    /// unless the entity has a [PathFollower](super::PathFollower)
+    pub vel: Vec2,
+
+    /// Angular velocity (in radians/s, counter-clockwise) around the
+    /// entity's origin, is not modified by the physics system
+    pub angular_vel: f32,
+
+    /// Whether dynamic objects, colliding with this body, are moved along
+    /// with it. Meant for nonsolid bodies, which objects are standing on
+    pub carry: bool,
+}
+
+impl KinematicBody {
+    /// Returns a kinematic body, that isn't moving
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the body with `vel` set
+    pub fn with_vel(mut self, vel: Vec2) -> Self {
+        self.vel = vel;
+        self
+    }
+
+    /// Returns the body with `angular_vel` set
+    pub fn with_angular_vel(mut self, angular_vel: f32) -> Self {
+        self.angular_vel = angular_vel;
+        self
+    }
+
+    /// Returns the body, that carries colliding dynamic objects
+    pub fn carrying(mut self) -> Self {
+        self.carry = true;
+        self
+    }
+
+    /// Velocity of the point of the body, which is at `offset` from its origin
+    #[inline]
+    pub(super) fn point_vel(&self, offset: Vec2) -> Vec2 {
+        self.vel + self.angular_vel * offset.perp()
+    }
+}
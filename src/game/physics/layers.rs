@@ -0,0 +1,55 @@
+use crate::config::physics::layers;
+
+/// Collision groups of a [Collider](super::Collider): bitmasks of layers
+/// it belongs to and of layers it collides with.
+/// Two colliders interact only if each of them belongs to a layer
+/// the other one collides with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CollisionLayers {
+    /// Layers that the collider belongs to
+    pub memberships: u32,
+    /// Layers that the collider collides with
+    pub filter: u32,
+}
+
+impl Default for CollisionLayers {
+    /// Belongs to the default layer and collides with everything
+    fn default() -> Self {
+        Self::new(layers::DEFAULT, layers::ALL)
+    }
+}
+
+impl CollisionLayers {
+    pub const fn new(memberships: u32, filter: u32) -> Self {
+        Self {
+            memberships,
+            filter,
+        }
+    }
+
+    /// Returns whether colliders with `self` and `other` layers interact
+    #[inline]
+    pub fn interacts_with(&self, other: &CollisionLayers) -> bool {
+        self.memberships & other.filter != 0
+            && other.memberships & self.filter != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interacts_with_test() {
+        const PICKUP: u32 = 1 << 10;
+        const ENEMY: u32 = 1 << 11;
+        let player = CollisionLayers::new(layers::PLAYER, layers::ALL);
+        let pickup = CollisionLayers::new(PICKUP, layers::PLAYER);
+        let enemy = CollisionLayers::new(ENEMY, layers::ALL);
+        assert!(player.interacts_with(&pickup));
+        assert!(pickup.interacts_with(&player));
+        assert!(!pickup.interacts_with(&enemy));
+        assert!(!enemy.interacts_with(&pickup));
+        assert!(CollisionLayers::default().interacts_with(&enemy));
+    }
+}
@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+
+/// How a property of two materials in contact is combined into one.
+/// If the two materials have different rules, the one that comes later
+/// in this list is used.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "debug", derive(bevy_inspector_egui::Inspectable))]
+pub enum CombineRule {
+    Average,
+    Min,
+    Multiply,
+    Max,
+}
+
+impl CombineRule {
+    fn combine(self, a: f32, b: f32) -> f32 {
+        match self {
+            CombineRule::Average => (a + b) * 0.5,
+            CombineRule::Min => a.min(b),
+            CombineRule::Multiply => a * b,
+            CombineRule::Max => a.max(b),
+        }
+    }
+}
+
+/// Component, describing the physical properties of a collider.
+/// Colliders without it behave as if they had
+/// [PhysicsConfig::default_material](super::PhysicsConfig::default_material).
+#[derive(Component, Copy, Clone, Debug)]
+#[cfg_attr(feature = "debug", derive(bevy_inspector_egui::Inspectable))]
+pub struct PhysicsMaterial {
+    /// Mass per unit of area. If set on a dynamic object, its mass is
+    /// computed from the area of its collider, overriding the mass
+    /// of [DynamicObject](super::DynamicObject)
+    #[cfg_attr(feature = "debug", inspectable(ignore))]
+    pub density: Option<f32>,
+    /// Part of the normal velocity that is kept after a bounce:
+    /// 0 - no bounce at all, 1 - perfectly elastic bounce
+    pub restitution: f32,
+    /// Coulomb friction coefficient of the surface
+    pub friction: f32,
+    pub restitution_combine: CombineRule,
+    pub friction_combine: CombineRule,
+}
+
+impl PhysicsMaterial {
+    /// Material with given restitution and friction, which are averaged
+    /// with the other material's
+    pub fn new(restitution: f32, friction: f32) -> Self {
+        assert!(restitution >= 0.0 && friction >= 0.0);
+        Self {
+            density: None,
+            restitution,
+            friction,
+            restitution_combine: CombineRule::Average,
+            friction_combine: CombineRule::Average,
+        }
+    }
+
+    /// Returns the material with `density` set. `density` must be positive
+    pub fn with_density(mut self, density: f32) -> Self {
+        assert!(density > 0.0, "Non-positive density = {}", density);
+        self.density = Some(density);
+        self
+    }
+
+    /// Returns the material with combine rules set
+    pub fn with_combine_rules(
+        mut self,
+        restitution_combine: CombineRule,
+        friction_combine: CombineRule,
+    ) -> Self {
+        self.restitution_combine = restitution_combine;
+        self.friction_combine = friction_combine;
+        self
+    }
+
+    /// Restitution of the contact between `self` and `other`
+    pub fn combined_restitution(&self, other: &PhysicsMaterial) -> f32 {
+        self.restitution_combine
+            .max(other.restitution_combine)
+            .combine(self.restitution, other.restitution)
+    }
+
+    /// Friction coefficient of the contact between `self` and `other`
+    pub fn combined_friction(&self, other: &PhysicsMaterial) -> f32 {
+        self.friction_combine
+            .max(other.friction_combine)
+            .combine(self.friction, other.friction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combine_test() {
+        let rubber = PhysicsMaterial::new(0.9, 1.0)
+            .with_combine_rules(CombineRule::Max, CombineRule::Average);
+        let ice = PhysicsMaterial::new(0.1, 0.0)
+            .with_combine_rules(CombineRule::Min, CombineRule::Multiply);
+        // `Max` takes precedence over `Min`
+        assert_eq!(rubber.combined_restitution(&ice), 0.9);
+        assert_eq!(ice.combined_restitution(&rubber), 0.9);
+        // `Multiply` takes precedence over `Average`
+        assert_eq!(rubber.combined_friction(&ice), 0.0);
+        assert_eq!(
+            rubber.combined_friction(&PhysicsMaterial::new(0.0, 0.5)),
+            0.75
+        );
+    }
+}
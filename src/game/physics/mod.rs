@@ -2,133 +2,957 @@
 //! Every entity that can collide with any other object has [Collider] component.
 //! Every [Collider] entity that has [DynamicObject] component is considered a
 //! "dynamic collider object", otherwise it's a "static collider object".
+//! Static collider objects with [KinematicBody] component are moved
+//! by their velocity.
+//! Colliders are placed with global transforms, but dynamic objects and
+//! kinematic bodies are moved in their parent's space, so they are meant to be
+//! top-level entities. Colliders, attached to their parent's collider, form
+//! a compound collider with it instead of being objects on their own,
+//! while the other child colliders don't collide with their ancestors.
+//! Dynamic objects, that rest for a while, fall asleep and are skipped
+//! until they are pushed, hit or woken up with [DynamicObject::wake_up].
+//! The physics is added to an app with [PhysicsPlugin] and tuned at runtime
+//! with the [PhysicsConfig] resource.
 //!
 pub use bounding_box::BoundingBox;
 pub use collider::{Collider, Collision};
+pub use config::PhysicsConfig;
+pub use controller::ControllerMode;
 pub use dynamic_object::DynamicObject;
+pub use events::{
+    send_collision_events, CollisionData, CollisionEnded, CollisionOngoing,
+    CollisionStarted,
+};
+pub use force_field::ForceField;
+pub use interpolation::Interpolation;
+pub use joint::{Joint, JointKind};
+pub use kinematic_body::KinematicBody;
+pub use layers::CollisionLayers;
+pub use material::{CombineRule, PhysicsMaterial};
+pub use path::{Easing, PathFollower, PathMode};
+pub use plugin::{PhysicsLabel, PhysicsPlugin};
+pub use query::{CastHit, PhysicsQuery, ProximityHit};
+pub use surface::Surface;
+pub use time::PhysicsTime;
+pub use trigger::{
+    send_trigger_events, Trigger, TriggerEntered, TriggerExited, TriggerStayed,
+};
 
+pub mod gjk;
 pub mod shape;
 pub mod util;
 
 mod bounding_box;
 mod collider;
+mod config;
+mod controller;
 mod dynamic_object;
+mod events;
+mod force_field;
+mod interpolation;
+mod island;
+mod joint;
+mod kinematic_body;
+mod layers;
+mod material;
+mod path;
+mod plugin;
+mod query;
+mod solver;
+mod surface;
+#[cfg(test)]
+mod test_util;
+mod time;
+mod trigger;
 
+use std::collections::BTreeMap;
+
+use bevy::ecs::system::SystemParam;
+use bevy::math::Mat2;
 use bevy::prelude::*;
+use bevy::tasks::{ComputeTaskPool, TaskPool};
+use itertools::Itertools;
+
+use crate::util::{QuatExt, TransformExt};
+
+use collider::AttachedPart;
+
+/// Dynamic collider objects
+type DynObjectQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut Transform,
+        &'static mut Collider,
+        &'static mut DynamicObject,
+    ),
+>;
+
+/// Static collider objects
+type StatObjectQuery<'w, 's> = Query<
+    'w,
+    's,
+    (Entity, &'static mut Transform, &'static mut Collider),
+    Without<DynamicObject>,
+>;
+
+/// All objects, that take part in a physics step
+#[derive(SystemParam)]
+pub struct PhysicsObjects<'w, 's> {
+    dyn_object_query: DynObjectQuery<'w, 's>,
+    stat_object_query: StatObjectQuery<'w, 's>,
+    kinematic_query: Query<'w, 's, &'static mut KinematicBody>,
+    path_query: Query<'w, 's, &'static mut PathFollower>,
+    force_field_query: Query<'w, 's, &'static ForceField>,
+    surface_query: Query<'w, 's, &'static Surface>,
+    material_query: Query<'w, 's, &'static PhysicsMaterial>,
+    parent_query: Query<'w, 's, &'static Parent>,
+    transform_query: Query<'w, 's, &'static Transform, Without<Collider>>,
+    joint_query: Query<'w, 's, (Entity, &'static Joint)>,
+}
 
-use crate::config::physics;
-use crate::util::{TransformExt, Vec2Ext};
+/// Pre-step system, that restores the transforms of the last step,
+/// which were interpolated for rendering
+pub fn restore_transforms(
+    mut interpolation_query: Query<(&mut Interpolation, &mut Transform)>,
+) {
+    for (mut interpolation, mut transform) in interpolation_query.iter_mut() {
+        interpolation.restore(&mut transform);
+    }
+}
 
-/// Main physics system, moves all dynamic objects and processes collisions
+/// Main physics system, moves all kinematic and dynamic objects
+/// and processes collisions in steps of [PhysicsConfig::timestep].
+/// Several steps are done in one frame if needed, and none if the frame
+/// was shorter than the step or [PhysicsTime] is paused
 pub fn update(
     time: Res<Time>,
-    mut dyn_object_query: Query<(
-        Entity,
-        &mut Transform,
-        &mut Collider,
-        &mut DynamicObject,
-    )>,
-    mut stat_object_query: Query<
-        (Entity, &Transform, &mut Collider),
-        Without<DynamicObject>,
-    >,
+    pool: Res<ComputeTaskPool>,
+    config: Res<PhysicsConfig>,
+    mut physics_time: ResMut<PhysicsTime>,
+    mut objects: PhysicsObjects,
+    mut interpolation_query: Query<(Entity, &mut Interpolation)>,
 ) {
-    /*** Movement ***/
-    for (_entity, mut transform, _collider, mut dynamic_object) in
-        dyn_object_query.iter_mut()
-    {
-        let delta = time.delta_seconds();
+    let steps = physics_time.advance(time.delta_seconds(), &config);
+    if steps > 0 {
+        objects.clear_recent_collisions();
+    }
+    for step_index in 0..steps {
+        if step_index + 1 == steps {
+            for (entity, mut interpolation) in interpolation_query.iter_mut() {
+                if let Some(transform) = objects.transform_mut(entity) {
+                    interpolation.start_step(&transform);
+                }
+            }
+        }
+        objects.step(&config, &pool);
+    }
+}
 
-        let dynamic_object = &mut *dynamic_object;
+/// Post-step system, that interpolates the transforms of the objects
+/// with [Interpolation] by the time, left over from the steps
+pub fn interpolate_transforms(
+    config: Res<PhysicsConfig>,
+    physics_time: Res<PhysicsTime>,
+    mut interpolation_query: Query<(&mut Interpolation, &mut Transform)>,
+) {
+    let alpha = physics_time.alpha(&config);
+    for (mut interpolation, mut transform) in interpolation_query.iter_mut() {
+        if physics_time.last_steps > 0 {
+            interpolation.end_step(&transform);
+        }
+        interpolation.interpolate(&mut transform, alpha);
+    }
+}
 
-        // Apply friction
-        let total_accel = dynamic_object.accel
-            - dynamic_object.vel * dynamic_object.friction_coeff;
-        // Apply the aceleration
-        dynamic_object.vel += total_accel * delta;
-        // Clamp the velocity to `max_vel`
-        if dynamic_object.vel.length_squared() > dynamic_object.max_vel_squared
-        {
-            dynamic_object.vel *= f32::sqrt(
-                dynamic_object.max_vel_squared
-                    / dynamic_object.vel.length_squared(),
-            );
+/// Post-step system, that clears the forces of dynamic objects.
+/// Forces are applied during all steps of the frame, in which they were
+/// applied, or of the next frame with any steps
+pub fn clear_forces(
+    physics_time: Res<PhysicsTime>,
+    mut dyn_object_query: Query<&mut DynamicObject>,
+) {
+    if physics_time.last_steps == 0 {
+        return;
+    }
+    for mut dyn_object in dyn_object_query.iter_mut() {
+        if dyn_object.force != Vec2::ZERO {
+            dyn_object.force = Vec2::ZERO;
         }
-        // Apply the velocity
-        transform.translate(dynamic_object.vel * delta);
     }
+}
 
-    /**** Collision ****/
-    // Update colliders
-    for (_entity, transform, mut collider, _dyn_object) in
-        dyn_object_query.iter_mut()
-    {
-        collider.update(&transform);
+impl<'w, 's> PhysicsObjects<'w, 's> {
+    /// Transform of a dynamic or a static object
+    fn transform_mut(&mut self, entity: Entity) -> Option<Mut<'_, Transform>> {
+        if let Ok(transform) =
+            self.dyn_object_query.get_component_mut::<Transform>(entity)
+        {
+            return Some(transform);
+        }
+        self.stat_object_query
+            .get_component_mut::<Transform>(entity)
+            .ok()
     }
-    for (_entity, transform, mut collider) in stat_object_query.iter_mut() {
-        collider.update(transform);
+
+    /// Clears the collisions of all colliders, that are collected
+    /// over the steps of a frame
+    fn clear_recent_collisions(&mut self) {
+        for (.., mut collider, _) in self.dyn_object_query.iter_mut() {
+            collider.clear_recent_collisions();
+        }
+        for (.., mut collider) in self.stat_object_query.iter_mut() {
+            collider.clear_recent_collisions();
+        }
     }
 
-    // Process collisions between all pairs of a dynamic and a static object
-    for (entity1, transform1, mut collider1, _dyn_object) in
-        dyn_object_query.iter_mut()
-    {
-        for (entity2, transform2, mut collider2) in stat_object_query.iter_mut()
+    /// Moves all kinematic and dynamic objects by one timestep
+    /// and processes collisions.
+    /// Objects and pairs of them are processed in the order of their entities,
+    /// so that the results don't depend on the order of the queries
+    fn step(&mut self, config: &PhysicsConfig, pool: &TaskPool) {
+        let delta = config.timestep;
+        let PhysicsObjects {
+            dyn_object_query,
+            stat_object_query,
+            kinematic_query,
+            path_query,
+            force_field_query,
+            surface_query,
+            material_query,
+            parent_query,
+            transform_query,
+            joint_query,
+        } = self;
+        let dyn_entities: Vec<Entity> = dyn_object_query
+            .iter()
+            .map(|(entity, ..)| entity)
+            .sorted()
+            .collect();
+        let stat_entities: Vec<Entity> = stat_object_query
+            .iter()
+            .filter(|(_, _, collider)| !collider.attached)
+            .map(|(entity, ..)| entity)
+            .sorted()
+            .collect();
+
+        /*** Compound colliders ***/
+        // Attached colliders' shapes by their parents
+        let mut attached_parts: BTreeMap<Entity, Vec<AttachedPart>> =
+            BTreeMap::new();
+        for (entity, transform, collider) in stat_object_query
+            .iter()
+            .filter(|(_, _, collider)| collider.attached)
+            .sorted_by_key(|(entity, ..)| *entity)
         {
-            if let Some(mut mpv) = collider1.process_collision(&collider2) {
-                // Sometimes the mpv might be in the wrong direction
-                // Simple (but wrong) fix
-                if mpv.dot(
-                    (transform2.translation - transform1.translation)
-                        .truncate(),
-                ) < 0.0
+            if let Ok(parent) = parent_query.get(entity) {
+                attached_parts
+                    .entry(parent.0)
+                    .or_default()
+                    .push(collider.attached_part(*transform));
+            }
+        }
+        for (entity, _, mut collider, _) in dyn_object_query.iter_mut() {
+            collider.set_attached_parts(
+                attached_parts.remove(&entity).unwrap_or_default(),
+            );
+        }
+        for &entity in &stat_entities {
+            stat_object_query
+                .get_component_mut::<Collider>(entity)
+                .unwrap()
+                .set_attached_parts(
+                    attached_parts.remove(&entity).unwrap_or_default(),
+                );
+        }
+
+        /*** Kinematic movement ***/
+        // (Carried entity, carrier's origin before this step,
+        // carrier's displacement, carrier's rotation delta)
+        let mut carries: Vec<(Entity, Vec2, Vec2, f32)> = Vec::new();
+        for &entity in &stat_entities {
+            let mut kinematic = match kinematic_query.get_mut(entity) {
+                Ok(kinematic) => kinematic,
+                Err(_) => continue,
+            };
+            let (_, mut transform, collider) =
+                stat_object_query.get_mut(entity).unwrap();
+            if let Ok(mut path_follower) = path_query.get_mut(entity) {
+                kinematic.vel = path_follower
+                    .advance(delta, transform.translation.truncate());
+            }
+            let displacement = kinematic.vel * delta;
+            let rotation_delta = kinematic.angular_vel * delta;
+            if kinematic.carry {
+                // Collisions are still the ones of the previous step
+                for collision in collider.step_collisions() {
+                    carries.push((
+                        collision.other_entity,
+                        transform.translation.truncate(),
+                        displacement,
+                        rotation_delta,
+                    ));
+                }
+            }
+            if displacement != Vec2::ZERO {
+                transform.translate(displacement);
+            }
+            if rotation_delta != 0.0 {
+                QuatExt::rotate(&mut transform.rotation, rotation_delta);
+            }
+        }
+        // Move the carried objects as if they were attached to their carriers
+        for (entity, pivot, displacement, rotation_delta) in carries {
+            if let Ok((_, mut transform, _, mut dyn_object)) =
+                dyn_object_query.get_mut(entity)
+            {
+                if dyn_object.sleeping {
+                    dyn_object.wake_up();
+                }
+                let offset = transform.translation.truncate() - pivot;
+                let rotated_offset = Mat2::from_angle(rotation_delta) * offset;
+                transform.translate(displacement + rotated_offset - offset);
+                if rotation_delta != 0.0 {
+                    QuatExt::rotate(&mut transform.rotation, rotation_delta);
+                }
+            }
+        }
+
+        /*** Movement ***/
+        // Force fields with their colliders as of the previous step
+        let force_fields: Vec<(&ForceField, &Collider)> = stat_entities
+            .iter()
+            .filter_map(|&entity| {
+                Some((
+                    force_field_query.get(entity).ok()?,
+                    stat_object_query.get_component::<Collider>(entity).ok()?,
+                ))
+            })
+            .collect();
+        // Solid static colliders, that sliding objects stop at
+        let solids: Vec<&Collider> = stat_entities
+            .iter()
+            .map(|&entity| {
+                stat_object_query.get_component::<Collider>(entity).unwrap()
+            })
+            .filter(|collider| collider.solid)
+            .collect();
+        // (Rotated entity, its rotation before this step)
+        let mut rotations: Vec<(Entity, Quat)> = Vec::new();
+        // Whether the dynamic objects are sleeping, but have been moved
+        // since their colliders were placed, e.g. by gameplay code
+        let dyn_moved: Vec<bool> = dyn_entities
+            .iter()
+            .map(|&entity| {
+                let (_, _, collider, dynamic_object) =
+                    dyn_object_query.get(entity).unwrap();
+                dynamic_object.sleeping
+                    && !collider.is_placed_at(&global_transform(
+                        entity,
+                        parent_query,
+                        dyn_object_query,
+                        stat_object_query,
+                        transform_query,
+                    ))
+            })
+            .collect();
+        // Force field accelerations by dynamic objects
+        let mut field_accels = Vec::with_capacity(dyn_entities.len());
+        for (index, &entity) in dyn_entities.iter().enumerate() {
+            let (_, mut transform, collider, mut dynamic_object) =
+                dyn_object_query.get_mut(entity).unwrap();
+            let dynamic_object = &mut *dynamic_object;
+            let field_accel = force_field::total_accel(
+                &force_fields,
+                &collider,
+                transform.translation.truncate(),
+                dynamic_object.vel,
+            );
+            field_accels.push(field_accel);
+            // Sleeping objects stay in place, until they are pushed or moved
+            if dynamic_object.sleeping {
+                if dynamic_object.is_resting(field_accel, config)
+                    && !dyn_moved[index]
                 {
-                    mpv = -mpv;
+                    continue;
+                }
+                dynamic_object.wake_up();
+            }
+            let surface = surface::SurfaceParams::new(
+                dynamic_object,
+                &collider,
+                surface_query,
+            );
+
+            // Apply friction and force fields
+            let total_accel = dynamic_object.accel * surface.accel_scale
+                - dynamic_object.vel * surface.friction_coeff
+                + dynamic_object.force_accel()
+                + field_accel;
+            // Apply the aceleration
+            dynamic_object.vel += total_accel * delta;
+            dynamic_object.apply_drag(delta);
+            // Clamp the velocity to `max_vel` and the global maximum
+            let max_vel_squared = surface
+                .max_vel_squared
+                .min(config.global_max_vel * config.global_max_vel);
+            if dynamic_object.vel.length_squared() > max_vel_squared {
+                dynamic_object.vel *= f32::sqrt(
+                    max_vel_squared / dynamic_object.vel.length_squared(),
+                );
+            }
+            // Apply the velocity
+            match dynamic_object.controller {
+                ControllerMode::Bounce => {
+                    transform.translate(dynamic_object.vel * delta)
                 }
-                collider1.add_recent_collision(entity2, mpv);
-                collider2.add_recent_collision(entity1, -mpv);
+                ControllerMode::Slide {
+                    skin_width,
+                    max_iterations,
+                } => controller::move_and_slide(
+                    &collider,
+                    &mut transform,
+                    &mut dynamic_object.vel,
+                    delta,
+                    (skin_width, max_iterations),
+                    &solids,
+                ),
+            }
+
+            // Same for rotation
+            let total_angular_accel = dynamic_object.angular_accel
+                - dynamic_object.angular_vel
+                    * dynamic_object.angular_friction_coeff;
+            dynamic_object.angular_vel += total_angular_accel * delta;
+            dynamic_object.angular_vel = dynamic_object.angular_vel.clamp(
+                -config.global_max_angular_vel,
+                config.global_max_angular_vel,
+            );
+            // The rotation is part of the motion, so that the collisions it causes
+            // are resolved within the same step
+            let rotation_delta =
+                (dynamic_object.angular_vel + dynamic_object.spin) * delta;
+            if rotation_delta != 0.0 {
+                rotations.push((entity, transform.rotation));
+                QuatExt::rotate(&mut transform.rotation, rotation_delta);
             }
         }
-    }
 
-    /**** Bounce ****/
-    // (Which entity to bounce, bounce mpv)
-    let mut bounces: Vec<(Entity, Vec2)> = Vec::new();
+        /**** Collision ****/
+        // Update colliders. Static objects, that have moved, and awake
+        // dynamic objects can cause new collisions, while sleeping objects
+        // keep the collisions with the other objects that can't
+        // Child colliders are placed with their ancestors' current transforms,
+        // as the global transforms are only propagated after the physics
+        let global_transforms = |entities: &[Entity]| -> Vec<Transform> {
+            entities
+                .iter()
+                .map(|&entity| {
+                    global_transform(
+                        entity,
+                        parent_query,
+                        dyn_object_query,
+                        stat_object_query,
+                        transform_query,
+                    )
+                })
+                .collect()
+        };
+        let stat_transforms = global_transforms(&stat_entities);
+        let dyn_transforms = global_transforms(&dyn_entities);
+        let mut stat_moved = Vec::with_capacity(stat_entities.len());
+        for (&entity, transform) in stat_entities.iter().zip(&stat_transforms) {
+            let mut collider = stat_object_query
+                .get_component_mut::<Collider>(entity)
+                .unwrap();
+            stat_moved.push(!collider.is_placed_at(transform));
+            collider.update(transform);
+        }
+        let dyn_awake: Vec<bool> = dyn_entities
+            .iter()
+            .map(|&entity| {
+                !dyn_object_query
+                    .get_component::<DynamicObject>(entity)
+                    .unwrap()
+                    .sleeping
+            })
+            .collect();
+        let is_awake = |entity| match dyn_entities.binary_search(&entity) {
+            Ok(index) => dyn_awake[index],
+            Err(_) => match stat_entities.binary_search(&entity) {
+                Ok(index) => stat_moved[index],
+                Err(_) => false,
+            },
+        };
+        // (Static entity, its kept collision with a sleeping object)
+        let mut kept_collisions: Vec<(Entity, Collision)> = Vec::new();
+        for (index, &entity) in dyn_entities.iter().enumerate() {
+            let (_, _, mut collider, mut dyn_object) =
+                dyn_object_query.get_mut(entity).unwrap();
+            if !dyn_awake[index] {
+                collider.keep_step_collisions(|collision| {
+                    !is_awake(collision.other_entity)
+                });
+                for collision in collider.step_collisions() {
+                    if stat_entities
+                        .binary_search(&collision.other_entity)
+                        .is_ok()
+                    {
+                        kept_collisions.push((
+                            collision.other_entity,
+                            Collision {
+                                other_entity: entity,
+                                mpv: -collision.mpv,
+                                ..*collision
+                            },
+                        ));
+                    }
+                }
+                continue;
+            }
+            let transform = &dyn_transforms[index];
+            collider.update(transform);
+            if let Some(density) = material_query
+                .get(entity)
+                .ok()
+                .and_then(|material| material.density)
+            {
+                // A collider without enabled shapes keeps the last mass
+                let area = collider.area();
+                if area > 0.0 {
+                    dyn_object.mass = density * area;
+                }
+            }
+            // Inertia depends on the scale of the shapes, so update it as well
+            dyn_object.inertia = collider.moment_of_inertia(
+                dyn_object.mass,
+                transform.translation.truncate(),
+            );
+        }
+        for (entity, collision) in kept_collisions {
+            stat_object_query
+                .get_component_mut::<Collider>(entity)
+                .unwrap()
+                .add_recent_collision(
+                    collision.other_entity,
+                    collision.mpv,
+                    collision.contact,
+                    collision.impact_speed,
+                );
+        }
 
-    // Safety: no muts, so no mutable references are retreived
-    for (entity, _transform, collider, _dyn_object) in
-        unsafe { dyn_object_query.iter_unsafe() }
-    {
-        for collision in collider.get_recent_collisions() {
-            // Safety: no muts again
-            // TODO: stat_object_query -> concat(dyn + stat)
-            if let Ok(other_collider) = stat_object_query
-                .get_component::<Collider>(collision.other_entity)
+        // Find collisions between all pairs of a dynamic and a static object
+        // and all pairs of dynamic objects, at least one of which is awake
+        let dyn_colliders: Vec<(Entity, &Collider, bool)> = dyn_entities
+            .iter()
+            .zip(&dyn_awake)
+            .map(|(&entity, &awake)| {
+                (
+                    entity,
+                    dyn_object_query.get_component(entity).unwrap(),
+                    awake,
+                )
+            })
+            .collect();
+        let stat_colliders: Vec<(Entity, &Collider, bool)> = stat_entities
+            .iter()
+            .zip(&stat_moved)
+            .map(|(&entity, &moved)| {
+                (
+                    entity,
+                    stat_object_query.get_component(entity).unwrap(),
+                    moved,
+                )
+            })
+            .collect();
+        let mut collisions =
+            narrow_phase(pool, &dyn_colliders, &stat_colliders);
+        // A child collider, that is not attached, doesn't push its ancestors
+        collisions.retain(|&(entity1, entity2, ..)| {
+            !is_ancestor(entity1, entity2, parent_query)
+                && !is_ancestor(entity2, entity1, parent_query)
+        });
+
+        /**** Islands ****/
+        // Objects, touching each other or joined, form islands, which are
+        // woken up by any of their awake objects and objects that have moved
+        let joints: Vec<&Joint> = joint_query
+            .iter()
+            .sorted_by_key(|(entity, _)| *entity)
+            .map(|(_, joint)| joint)
+            .collect();
+        let mut islands = island::Islands::new(dyn_entities.len());
+        let mut awake = dyn_awake.clone();
+        for &(entity1, entity2, _, _, solid) in &collisions {
+            let body1 = dyn_entities.binary_search(&entity1).unwrap();
+            match dyn_entities.binary_search(&entity2) {
+                Ok(body2) if solid => islands.union(body1, body2),
+                Err(_) if solid => awake[body1] |= is_awake(entity2),
+                _ => {}
+            }
+        }
+        for joint in &joints {
+            if let (Ok(body1), Some(Ok(body2))) = (
+                dyn_entities.binary_search(&joint.body1),
+                joint
+                    .body2
+                    .map(|entity2| dyn_entities.binary_search(&entity2)),
+            ) {
+                islands.union(body1, body2);
+            }
+        }
+        let mut island_awake = vec![false; dyn_entities.len()];
+        for body in 0..dyn_entities.len() {
+            if awake[body] {
+                island_awake[islands.find(body)] = true;
+            }
+        }
+        for (body, &entity) in dyn_entities.iter().enumerate() {
+            if !awake[body] && island_awake[islands.find(body)] {
+                awake[body] = true;
+                dyn_object_query
+                    .get_component_mut::<DynamicObject>(entity)
+                    .unwrap()
+                    .wake_up();
+            }
+        }
+
+        /**** Contact solving ****/
+        let mut solver = solver::Solver::new(
+            dyn_entities
+                .iter()
+                .map(|&entity| {
+                    let (_, transform, _, dyn_object) =
+                        dyn_object_query.get(entity).unwrap();
+                    (dyn_object.clone(), transform.translation.truncate())
+                })
+                .collect(),
+            config,
+        );
+        let default_material = config.default_material();
+        let get_material =
+            |entity| material_query.get(entity).unwrap_or(&default_material);
+
+        for (entity1, entity2, mpv, point, solid) in collisions {
+            // `dyn_entities` are sorted, so the index of an entity can be found
+            let body1 = dyn_entities.binary_search(&entity1).unwrap();
+            let body2 = dyn_entities.binary_search(&entity2).ok();
+            let surface_vel = match stat_object_query.get(entity2) {
+                Ok((_, transform2, _)) => surface_vel(
+                    kinematic_query.get(entity2).ok(),
+                    transform2,
+                    point,
+                ),
+                Err(_) => Vec2::ZERO,
+            };
+            let material1 = get_material(entity1);
+            let material2 = get_material(entity2);
+            // Sliding objects neither bounce nor get slowed down by contacts
+            let slides = [Some(body1), body2].iter().flatten().any(|&body| {
+                solver.body(body).controller != ControllerMode::Bounce
+            });
+            let (restitution, friction) = if slides {
+                (0.0, 0.0)
+            } else {
+                (
+                    material1.combined_restitution(material2),
+                    material1.combined_friction(material2),
+                )
+            };
+            // To push the first object out of the second one, invert mpv
+            let contact = solver::Contact {
+                body1,
+                body2,
+                mpv: -mpv,
+                point,
+                surface_vel,
+                restitution,
+                friction,
+            };
+            let impact_speed = solver.approach_speed(&contact);
+
+            dyn_object_query
+                .get_component_mut::<Collider>(entity1)
+                .unwrap()
+                .add_recent_collision(entity2, mpv, point, impact_speed);
+            let mut collider2 =
+                match dyn_object_query.get_component_mut::<Collider>(entity2) {
+                    Ok(collider2) => collider2,
+                    Err(_) => stat_object_query
+                        .get_component_mut::<Collider>(entity2)
+                        .unwrap(),
+                };
+            collider2.add_recent_collision(entity1, -mpv, point, impact_speed);
+
+            if solid {
+                solver.add_contact(contact);
+            }
+        }
+
+        // Joints between awake dynamic objects or fixed points
+        for joint in joints {
+            let body1 = match dyn_entities.binary_search(&joint.body1) {
+                Ok(body1) if awake[body1] => body1,
+                _ => continue,
+            };
+            let offset1 = joint::anchor_offset(
+                dyn_object_query
+                    .get_component::<Transform>(joint.body1)
+                    .unwrap(),
+                joint.anchor1,
+            );
+            let (body2, offset2, anchor2) = match joint.body2 {
+                Some(entity2) => {
+                    let body2 = match dyn_entities.binary_search(&entity2) {
+                        Ok(body2) => body2,
+                        Err(_) => continue,
+                    };
+                    let offset2 = joint::anchor_offset(
+                        dyn_object_query
+                            .get_component::<Transform>(entity2)
+                            .unwrap(),
+                        joint.anchor2,
+                    );
+                    (Some(body2), offset2, solver.translation(body2) + offset2)
+                }
+                None => (None, Vec2::ZERO, joint.anchor2),
+            };
+            solver.add_joint(
+                joint::JointConstraint::new(
+                    joint.kind,
+                    (body1, body2),
+                    (offset1, offset2),
+                    (solver.translation(body1) + offset1, anchor2),
+                ),
+                delta,
+            );
+        }
+
+        solver.solve();
+        for (index, &entity) in dyn_entities.iter().enumerate() {
+            let (_, mut transform, _, mut dyn_object) =
+                dyn_object_query.get_mut(entity).unwrap();
+            let body = solver.body(index);
+            dyn_object.vel = body.vel;
+            dyn_object.angular_vel = body.angular_vel;
+            let correction = solver.correction(index);
+            if correction != Vec2::ZERO {
+                transform.translate(correction);
+            }
+        }
+
+        /**** Sleeping ****/
+        // Islands, all objects of which have been resting long enough,
+        // fall asleep
+        let mut island_resting = vec![true; dyn_entities.len()];
+        for (body, &entity) in dyn_entities.iter().enumerate() {
+            if !awake[body] {
+                continue;
+            }
+            let mut dyn_object = dyn_object_query
+                .get_component_mut::<DynamicObject>(entity)
+                .unwrap();
+            if dyn_object.is_resting(field_accels[body], config) {
+                dyn_object.rest_time += delta;
+            } else {
+                dyn_object.rest_time = 0.0;
+            }
+            if !dyn_object.can_sleep || dyn_object.rest_time < config.sleep_time
             {
-                if other_collider.solid {
-                    // To push self out of other (instead of other out of self), invert mpv
-                    bounces.push((entity, -collision.mpv));
+                island_resting[islands.find(body)] = false;
+            }
+        }
+        let mut fallen_asleep = Vec::new();
+        for (body, &entity) in dyn_entities.iter().enumerate() {
+            if awake[body] && island_resting[islands.find(body)] {
+                dyn_object_query
+                    .get_component_mut::<DynamicObject>(entity)
+                    .unwrap()
+                    .fall_asleep();
+                fallen_asleep.push(entity);
+            }
+        }
+
+        /**** Blocked rotation ****/
+        // Pushing the object out of several colliders at once (e.g. in a corner)
+        // may leave it inside one of them if it was rotated into them.
+        // In that case the rotation is blocked: it's undone for this step,
+        // and the object is pushed out once more.
+        for (entity, prev_rotation) in rotations {
+            let index = dyn_entities.binary_search(&entity).unwrap();
+            if !solver.has_contacts(index) {
+                continue;
+            }
+            let (_, mut transform, mut collider, _) =
+                dyn_object_query.get_mut(entity).unwrap();
+            collider.update_shapes(&transform);
+            if !penetrates_solid(
+                &collider,
+                stat_object_query,
+                config.penetration_slop,
+            ) {
+                continue;
+            }
+
+            transform.rotation = prev_rotation;
+            collider.update_shapes(&transform);
+            for &other_entity in &stat_entities {
+                let other_collider = stat_object_query
+                    .get_component::<Collider>(other_entity)
+                    .unwrap();
+                if !other_collider.solid {
+                    continue;
+                }
+                if let Some((mpv, _)) =
+                    other_collider.process_collision(&collider)
+                {
+                    transform.translate(mpv);
+                    collider.update_shapes(&transform);
                 }
             }
         }
+
+        // Colliders of the objects, that have fallen asleep, are placed
+        // with their final transforms, so that moving them wakes them up
+        for entity in fallen_asleep {
+            let transform = global_transform(
+                entity,
+                parent_query,
+                dyn_object_query,
+                stat_object_query,
+                transform_query,
+            );
+            dyn_object_query
+                .get_component_mut::<Collider>(entity)
+                .unwrap()
+                .update_shapes(&transform);
+        }
+    }
+}
+
+/// Finds collisions of every dynamic collider with the following ones
+/// and with all static colliders, skipping pairs where neither collider
+/// is marked as awake. Chunks of dynamic colliders are processed
+/// in parallel, and the results are in the same order as sequentially.
+/// Returns (dynamic entity, other entity, mpv to push the other one out of
+/// the dynamic one, contact point, whether both colliders are solid)
+fn narrow_phase(
+    pool: &TaskPool,
+    dyn_colliders: &[(Entity, &Collider, bool)],
+    stat_colliders: &[(Entity, &Collider, bool)],
+) -> Vec<(Entity, Entity, Vec2, Vec2, bool)> {
+    let chunk_size = (dyn_colliders.len() / pool.thread_num()).max(1);
+    let chunk_collisions = pool.scope(|scope| {
+        for (chunk_index, chunk) in dyn_colliders.chunks(chunk_size).enumerate()
+        {
+            scope.spawn(async move {
+                let mut collisions = Vec::new();
+                for (index, &(entity1, collider1, awake1)) in
+                    chunk.iter().enumerate()
+                {
+                    let index = chunk_index * chunk_size + index;
+                    for &(entity2, collider2, awake2) in
+                        dyn_colliders[index + 1..].iter().chain(stat_colliders)
+                    {
+                        if !(awake1 || awake2) {
+                            continue;
+                        }
+                        if let Some((mpv, contact)) =
+                            collider1.process_collision(collider2)
+                        {
+                            collisions.push((
+                                entity1,
+                                entity2,
+                                mpv,
+                                contact,
+                                collider1.solid && collider2.solid,
+                            ));
+                        }
+                    }
+                }
+                collisions
+            });
+        }
+    });
+    chunk_collisions.into_iter().flatten().collect()
+}
+
+/// Global transform of `entity`, built from the current transforms of it
+/// and its ancestors, which may have changed since the last transform
+/// propagation
+fn global_transform(
+    entity: Entity,
+    parent_query: &Query<&Parent>,
+    dyn_object_query: &DynObjectQuery,
+    stat_object_query: &StatObjectQuery,
+    transform_query: &Query<&Transform, Without<Collider>>,
+) -> Transform {
+    let current_transform = |entity| {
+        dyn_object_query
+            .get_component::<Transform>(entity)
+            .ok()
+            .or_else(|| {
+                stat_object_query.get_component::<Transform>(entity).ok()
+            })
+            .or_else(|| transform_query.get(entity).ok())
+            .copied()
+    };
+    let mut transform = current_transform(entity).unwrap_or_default();
+    let mut entity = entity;
+    while let Some(parent_transform) =
+        parent_query.get(entity).ok().and_then(|parent| {
+            entity = parent.0;
+            current_transform(entity)
+        })
+    {
+        transform = parent_transform.mul_transform(transform);
     }
+    transform
+}
 
-    for (entity, mpv) in bounces {
-        let (_, mut transform, _, mut dyn_object) =
-            dyn_object_query.get_mut(entity).unwrap();
-        // Push the object with mpv
-        // TODO maybe tranlsate more?
-        transform.translation += Vec3::from((mpv, 0.0));
-
-        // Reflect the velocity off of mpv
-        dyn_object.vel = dyn_object.vel.reflect(mpv);
-
-        // Reduce the component of the velocity, collinear with mpv,
-        // according to bounciness
-        let mpv_normalized = mpv.normalize();
-        let vel_proj = dyn_object.vel.dot(mpv_normalized) * mpv_normalized;
-        dyn_object.vel -= vel_proj * (1.0 - physics::BOUNCINESS);
+/// Whether `ancestor` is a parent of `entity`, or of one of its ancestors
+fn is_ancestor(
+    entity: Entity,
+    ancestor: Entity,
+    parent_query: &Query<&Parent>,
+) -> bool {
+    let mut entity = entity;
+    while let Ok(parent) = parent_query.get(entity) {
+        if parent.0 == ancestor {
+            return true;
+        }
+        entity = parent.0;
     }
+    false
+}
+
+/// Velocity of the surface of a static object at `contact`.
+/// Only kinematic bodies have non-zero one
+fn surface_vel(
+    kinematic: Option<&KinematicBody>,
+    transform: &Transform,
+    contact: Vec2,
+) -> Vec2 {
+    kinematic.map_or(Vec2::ZERO, |kinematic| {
+        kinematic.point_vel(contact - transform.translation.truncate())
+    })
+}
 
-    // TODO process collisions between dynamic objects
+/// Returns whether `collider` is still inside of any solid static collider,
+/// ignoring penetrations up to `penetration_slop`, left after pushing it out
+fn penetrates_solid(
+    collider: &Collider,
+    stat_object_query: &StatObjectQuery,
+    penetration_slop: f32,
+) -> bool {
+    stat_object_query
+        .iter()
+        .filter(|(_, _, other_collider)| {
+            other_collider.solid && !other_collider.attached
+        })
+        .filter_map(|(_, _, other_collider)| {
+            other_collider.process_collision(collider)
+        })
+        .any(|(mpv, _)| mpv.length_squared() > penetration_slop.powi(2))
 }
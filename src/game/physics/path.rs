@@ -0,0 +1,249 @@
+//! Paths, along which kinematic bodies are moved, e.g. obstacles
+//! drawn in the svg data layer
+use std::str::FromStr;
+
+use bevy::prelude::*;
+
+/// What happens when a [PathFollower] reaches the end of its path
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PathMode {
+    /// Moves from the end straight back to the start and goes around again
+    Loop,
+    /// Moves back and forth along the path
+    PingPong,
+    /// Stops at the end
+    Once,
+}
+
+impl FromStr for PathMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "loop" => Ok(Self::Loop),
+            "ping_pong" => Ok(Self::PingPong),
+            "once" => Ok(Self::Once),
+            _ => Err(format!("Unknown path mode {}", s)),
+        }
+    }
+}
+
+/// How the speed changes during one pass of the path
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    /// Speeds up from the start
+    EaseIn,
+    /// Slows down towards the end
+    EaseOut,
+    /// Speeds up from the start and slows down towards the end
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps the part of the pass in time to the part of the path, both in [0, 1]
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+impl FromStr for Easing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Self::Linear),
+            "ease_in" => Ok(Self::EaseIn),
+            "ease_out" => Ok(Self::EaseOut),
+            "ease_in_out" => Ok(Self::EaseInOut),
+            _ => Err(format!("Unknown easing {}", s)),
+        }
+    }
+}
+
+/// Component, that moves the entity's [KinematicBody](super::KinematicBody)
+/// along a path, by setting its velocity in every physics step.
+/// The entity is placed at the start of the path when it's at `origin`
+#[derive(Component, Clone, Debug)]
+pub struct PathFollower {
+    /// Points of the path relative to its first point
+    offsets: Vec<Vec2>,
+    /// Distance along the path to each of `offsets`
+    distances: Vec<f32>,
+    /// Translation of the entity at the start of the path
+    origin: Vec2,
+    mode: PathMode,
+    /// Speed (in world units/s), with which the path is passed
+    /// without easing
+    speed: f32,
+    easing: Easing,
+    /// Time since the start of the movement
+    time: f32,
+}
+
+impl PathFollower {
+    /// `points` are the world-space points of the path,
+    /// `origin` is the translation of the entity at the first one
+    pub fn new(
+        points: &[Vec2],
+        origin: Vec2,
+        mode: PathMode,
+        speed: f32,
+    ) -> Self {
+        assert!(!points.is_empty(), "Empty path");
+        assert!(speed > 0.0, "Non-positive path speed = {}", speed);
+        let mut offsets: Vec<Vec2> =
+            points.iter().map(|&point| point - points[0]).collect();
+        if mode == PathMode::Loop {
+            // Close the path
+            offsets.push(Vec2::ZERO);
+        }
+        let distances = offsets
+            .iter()
+            .scan((0.0, Vec2::ZERO), |(distance, prev), &offset| {
+                *distance += prev.distance(offset);
+                *prev = offset;
+                Some(*distance)
+            })
+            .collect();
+        Self {
+            offsets,
+            distances,
+            origin,
+            mode,
+            speed,
+            easing: Easing::Linear,
+            time: 0.0,
+        }
+    }
+
+    /// Returns the follower with `easing` set
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    /// Moves the follower `delta` seconds forward along the path.
+    /// Returns the velocity, that moves the entity from `translation`
+    /// to the new point of the path in this time
+    pub(super) fn advance(&mut self, delta: f32, translation: Vec2) -> Vec2 {
+        self.time += delta;
+        (self.translation_at(self.time) - translation) / delta
+    }
+
+    /// Total length of the path
+    fn length(&self) -> f32 {
+        *self.distances.last().unwrap()
+    }
+
+    /// Translation of the entity `time` seconds after the start
+    fn translation_at(&self, time: f32) -> Vec2 {
+        let pass_time = self.length() / self.speed;
+        if pass_time == 0.0 {
+            return self.origin;
+        }
+        let passes = time / pass_time;
+        // Part of the current pass, and whether it's along the path
+        let (part, forward) = match self.mode {
+            PathMode::Loop => (passes.fract(), true),
+            PathMode::PingPong => {
+                let part = passes.rem_euclid(2.0);
+                if part <= 1.0 {
+                    (part, true)
+                } else {
+                    (2.0 - part, false)
+                }
+            }
+            PathMode::Once => (passes.min(1.0), true),
+        };
+        // Easing is mirrored on the way back
+        let part = if forward {
+            self.easing.apply(part)
+        } else {
+            1.0 - self.easing.apply(1.0 - part)
+        };
+        self.origin + self.offset_at(part * self.length())
+    }
+
+    /// Point of the path at `distance` along it
+    fn offset_at(&self, distance: f32) -> Vec2 {
+        let index = self
+            .distances
+            .iter()
+            .position(|&point_distance| point_distance >= distance)
+            .unwrap_or(self.distances.len() - 1);
+        if index == 0 {
+            return self.offsets[0];
+        }
+        let (start, end) = (self.distances[index - 1], self.distances[index]);
+        let t = if end > start {
+            (distance - start) / (end - start)
+        } else {
+            0.0
+        };
+        self.offsets[index - 1].lerp(self.offsets[index], t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPS: f32 = 1e-4;
+
+    fn path() -> Vec<Vec2> {
+        vec![
+            Vec2::new(10.0, 0.0),
+            Vec2::new(20.0, 0.0),
+            Vec2::new(20.0, 10.0),
+        ]
+    }
+
+    #[test]
+    fn translation_at_test() {
+        let origin = Vec2::new(1.0, 1.0);
+        let follower =
+            PathFollower::new(&path(), origin, PathMode::PingPong, 10.0);
+        assert!(follower.translation_at(0.0).abs_diff_eq(origin, EPS));
+        assert!(follower
+            .translation_at(1.5)
+            .abs_diff_eq(origin + Vec2::new(10.0, 5.0), EPS));
+        // On the way back
+        assert!(follower
+            .translation_at(3.5)
+            .abs_diff_eq(origin + Vec2::new(5.0, 0.0), EPS));
+
+        let follower = PathFollower::new(&path(), origin, PathMode::Once, 10.0);
+        assert!(follower
+            .translation_at(100.0)
+            .abs_diff_eq(origin + Vec2::new(10.0, 10.0), EPS));
+
+        // Goes back to the start along the closing segment
+        let follower = PathFollower::new(&path(), origin, PathMode::Loop, 10.0);
+        let closing = 200.0_f32.sqrt();
+        let time = (20.0 + closing * 0.5) / 10.0;
+        assert!(follower
+            .translation_at(time)
+            .abs_diff_eq(origin + Vec2::new(5.0, 5.0), EPS));
+    }
+
+    #[test]
+    fn easing_test() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert!(easing.apply(0.0).abs() < EPS);
+            assert!((easing.apply(1.0) - 1.0).abs() < EPS);
+        }
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+    }
+}
@@ -0,0 +1,327 @@
+use bevy::ecs::schedule::StateData;
+use bevy::prelude::*;
+
+use super::{
+    CollisionEnded, CollisionOngoing, CollisionStarted, PhysicsConfig,
+    PhysicsTime, TriggerEntered, TriggerExited, TriggerStayed,
+};
+
+/// Labels of the physics systems, in the order they run in.
+/// Systems, that move objects or change their velocities, should run
+/// before [PhysicsLabel::PreStep], and the ones, that read the results
+/// (e.g. the camera), after [PhysicsLabel::PostStep]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, SystemLabel)]
+pub enum PhysicsLabel {
+    /// Restores the transforms, that were interpolated for rendering
+    PreStep,
+    /// Does the physics steps
+    Step,
+    /// Interpolates the transforms and clears the forces
+    PostStep,
+    /// Sends collision events
+    Events,
+    /// Sends trigger events, derived from the collision events
+    TriggerEvents,
+}
+
+/// Plugin, that adds the physics resources, events and systems.
+/// Needs only `Time` and `ComputeTaskPool` resources, e.g. of `CorePlugin`,
+/// so it can be used in any app, including headless ones.
+/// [PhysicsConfig] and [PhysicsTime] are kept, if they were inserted before
+pub struct PhysicsPlugin<S = ()> {
+    /// State, in which the physics systems run, or `None` to always run them
+    state: Option<S>,
+}
+
+impl PhysicsPlugin {
+    /// Returns the plugin, whose systems always run
+    pub fn new() -> Self {
+        Self { state: None }
+    }
+}
+
+impl Default for PhysicsPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S: StateData> PhysicsPlugin<S> {
+    /// Returns the plugin, whose systems run only in `state`, so that
+    /// neither the objects move nor the physics time passes in other states
+    pub fn in_state(state: S) -> Self {
+        Self { state: Some(state) }
+    }
+
+    /// Empty system set, that runs in the plugin's state
+    fn system_set(&self) -> SystemSet {
+        match &self.state {
+            Some(state) => SystemSet::on_update(state.clone()),
+            None => SystemSet::new(),
+        }
+    }
+}
+
+impl<S: StateData> Plugin for PhysicsPlugin<S> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PhysicsConfig>()
+            .init_resource::<PhysicsTime>()
+            .add_event::<CollisionStarted>()
+            .add_event::<CollisionOngoing>()
+            .add_event::<CollisionEnded>()
+            .add_event::<TriggerEntered>()
+            .add_event::<TriggerStayed>()
+            .add_event::<TriggerExited>()
+            .add_system_set(
+                self.system_set()
+                    .label(PhysicsLabel::PreStep)
+                    .with_system(super::restore_transforms),
+            )
+            .add_system_set(
+                self.system_set()
+                    .label(PhysicsLabel::Step)
+                    .after(PhysicsLabel::PreStep)
+                    .with_system(super::update),
+            )
+            .add_system_set(
+                self.system_set()
+                    .label(PhysicsLabel::PostStep)
+                    .after(PhysicsLabel::Step)
+                    .with_system(super::interpolate_transforms)
+                    .with_system(super::clear_forces),
+            )
+            .add_system_set(
+                self.system_set()
+                    .label(PhysicsLabel::Events)
+                    .after(PhysicsLabel::PostStep)
+                    .with_system(super::send_collision_events),
+            )
+            .add_system_set(
+                self.system_set()
+                    .label(PhysicsLabel::TriggerEvents)
+                    .after(PhysicsLabel::Events)
+                    .with_system(super::send_trigger_events),
+            );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::app::Events;
+
+    use crate::game::physics::test_util::square;
+    use crate::game::physics::{
+        Collider, DynamicObject, ForceField, KinematicBody, PathFollower,
+        PathMode,
+    };
+
+    /// Headless app with `plugin`, whose physics time is paused
+    /// with `steps` requested, so that they don't depend on the real time
+    fn test_app<S: StateData>(plugin: PhysicsPlugin<S>, steps: u32) -> App {
+        let mut app = App::new();
+        app.add_plugin(bevy::core::CorePlugin).add_plugin(plugin);
+        let mut physics_time = PhysicsTime::new();
+        physics_time.pause();
+        physics_time.step(steps);
+        app.insert_resource(physics_time);
+        app
+    }
+
+    /// Spawns a static object with a solid square collider
+    /// with `half_size`, centered at `x` on the x axis
+    fn spawn_square(app: &mut App, x: f32, half_size: f32) -> Entity {
+        app.world
+            .spawn()
+            .insert(Transform::from_xyz(x, 0.0, 0.0))
+            .insert(Collider::solid_from_shapes(vec![square(
+                Vec2::ZERO,
+                half_size,
+            )]))
+            .id()
+    }
+
+    /// Spawns a dynamic object with a solid unit square collider
+    /// at the origin, moving with `vel`
+    fn spawn_moving_square(app: &mut App, vel: Vec2) -> Entity {
+        let mut dyn_object = DynamicObject::new();
+        dyn_object.set_velocity(vel);
+        let object = spawn_square(app, 0.0, 1.0);
+        app.world.entity_mut(object).insert(dyn_object);
+        object
+    }
+
+    #[test]
+    fn headless_test() {
+        let mut app = test_app(PhysicsPlugin::new(), 5);
+        let mut dyn_object = DynamicObject::new();
+        dyn_object.accel = Vec2::new(100.0, 0.0);
+        let object = spawn_square(&mut app, 0.0, 1.0);
+        app.world.entity_mut(object).insert(dyn_object);
+        let wall = spawn_square(&mut app, 1.5, 1.0);
+        app.update();
+
+        let x = app.world.get::<Transform>(object).unwrap().translation.x;
+        assert!(x < 0.0, "{}", x);
+        let started = app.world.get_resource::<Events<CollisionStarted>>();
+        let mut reader = started.unwrap().get_reader();
+        assert!(reader
+            .iter(started.unwrap())
+            .any(|CollisionStarted(data)| data.entities == (object, wall)));
+    }
+
+    #[test]
+    fn paused_path_test() {
+        let mut app = test_app(PhysicsPlugin::new(), 0);
+        let path = [Vec2::ZERO, Vec2::new(100.0, 0.0)];
+        let speed = 1.0 / PhysicsConfig::default().timestep;
+        let obstacle = spawn_square(&mut app, 0.0, 1.0);
+        app.world
+            .entity_mut(obstacle)
+            .insert(KinematicBody::new())
+            .insert(PathFollower::new(
+                &path,
+                Vec2::ZERO,
+                PathMode::Once,
+                speed,
+            ));
+
+        // Paths are followed only in the requested steps
+        app.update();
+        app.world.get_resource_mut::<PhysicsTime>().unwrap().step(3);
+        app.update();
+        app.update();
+        let x = app.world.get::<Transform>(obstacle).unwrap().translation.x;
+        assert!((x - 3.0).abs() < 1e-3, "{}", x);
+    }
+
+    #[test]
+    fn multi_step_collision_test() {
+        let mut app = test_app(PhysicsPlugin::new(), 5);
+        // Bounces off the wall in the first step and leaves it
+        let object = spawn_moving_square(&mut app, Vec2::new(100.0, 0.0));
+        spawn_square(&mut app, 2.5, 1.0);
+        app.update();
+
+        let vel = app.world.get::<DynamicObject>(object).unwrap().velocity();
+        assert!(vel.x < 0.0, "{}", vel);
+        let started = app.world.get_resource::<Events<CollisionStarted>>();
+        let mut reader = started.unwrap().get_reader();
+        assert_eq!(reader.iter(started.unwrap()).count(), 1);
+    }
+
+    #[test]
+    fn child_collider_test() {
+        let mut app = test_app(PhysicsPlugin::new(), 1);
+        let object = spawn_moving_square(&mut app, Vec2::new(100.0, 0.0));
+        let child = spawn_square(&mut app, 0.5, 1.0);
+        app.world.entity_mut(object).push_children(&[child]);
+
+        // The overlapping child neither pushes nor stops its parent
+        app.update();
+        let vel = app.world.get::<DynamicObject>(object).unwrap().velocity();
+        assert_eq!(vel, Vec2::new(100.0, 0.0));
+    }
+
+    #[test]
+    fn force_field_sleep_test() {
+        let mut app = test_app(PhysicsPlugin::new(), 0);
+        let object = spawn_moving_square(&mut app, Vec2::ZERO);
+        app.world
+            .spawn()
+            .insert(Transform::identity())
+            .insert(Collider::nonsolid_from_shapes(vec![square(
+                Vec2::ZERO,
+                10.0,
+            )]))
+            .insert(ForceField::Uniform {
+                accel: Vec2::new(10.0, 0.0),
+            });
+        spawn_square(&mut app, 2.5, 1.0);
+
+        // The object, pressed against the wall by the field, stays awake
+        let config = PhysicsConfig::default();
+        let steps = (2.0 * config.sleep_time / config.timestep) as u32;
+        for _ in 0..steps {
+            app.world.get_resource_mut::<PhysicsTime>().unwrap().step(1);
+            app.update();
+            let dyn_object = app.world.get::<DynamicObject>(object).unwrap();
+            assert!(!dyn_object.is_sleeping());
+        }
+    }
+
+    #[test]
+    fn moved_sleeping_test() {
+        let mut app = test_app(PhysicsPlugin::new(), 0);
+        let object = spawn_moving_square(&mut app, Vec2::ZERO);
+        let config = PhysicsConfig::default();
+        let steps = (config.sleep_time / config.timestep) as u32 + 2;
+        for _ in 0..steps {
+            app.world.get_resource_mut::<PhysicsTime>().unwrap().step(1);
+            app.update();
+        }
+        assert!(app
+            .world
+            .get::<DynamicObject>(object)
+            .unwrap()
+            .is_sleeping());
+
+        // Moving the object directly wakes it up and moves its collider
+        app.world
+            .get_mut::<Transform>(object)
+            .unwrap()
+            .translation
+            .x = 5.0;
+        app.world.get_resource_mut::<PhysicsTime>().unwrap().step(1);
+        app.update();
+        assert!(!app
+            .world
+            .get::<DynamicObject>(object)
+            .unwrap()
+            .is_sleeping());
+        let collider = app.world.get::<Collider>(object).unwrap();
+        assert!(collider.contains_point(Vec2::new(5.5, 0.0)));
+    }
+
+    #[test]
+    fn disabled_shapes_test() {
+        let mut app = test_app(PhysicsPlugin::new(), 1);
+        let object = spawn_moving_square(&mut app, Vec2::new(100.0, 0.0));
+        app.world
+            .get_mut::<Collider>(object)
+            .unwrap()
+            .set_shape_enabled(0, false);
+        spawn_square(&mut app, 1.5, 1.0);
+
+        // The object without enabled shapes passes through the wall
+        app.update();
+        let vel = app.world.get::<DynamicObject>(object).unwrap().velocity();
+        assert_eq!(vel, Vec2::new(100.0, 0.0));
+    }
+
+    #[test]
+    fn state_test() {
+        #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+        enum TestState {
+            Menu,
+            Game,
+        }
+        let mut app = test_app(PhysicsPlugin::in_state(TestState::Game), 1);
+        app.add_state(TestState::Menu);
+        let object = spawn_moving_square(&mut app, Vec2::new(100.0, 0.0));
+
+        // The requested step is kept until the game state
+        app.update();
+        let x = app.world.get::<Transform>(object).unwrap().translation.x;
+        assert_eq!(x, 0.0);
+        app.world
+            .get_resource_mut::<State<TestState>>()
+            .unwrap()
+            .set(TestState::Game)
+            .unwrap();
+        app.update();
+        app.update();
+        let x = app.world.get::<Transform>(object).unwrap().translation.x;
+        assert!(x > 0.0, "{}", x);
+    }
+}
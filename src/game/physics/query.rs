@@ -0,0 +1,315 @@
+//! Queries to the physics world: what does a ray or a moving shape hit,
+//! which colliders are at a point, in a box or overlap a shape,
+//! and how far a shape is from the colliders.
+//! All queries use the global shapes of colliders as of the last
+//! `physics::update()`.
+use bevy::ecs::system::SystemParam;
+use bevy::math::Vec2;
+use bevy::prelude::*;
+
+use crate::util::TransformExt;
+
+use super::shape::{PolyShape, Shape};
+use super::{BoundingBox, Collider, CollisionLayers};
+
+/// Result of a ray or shape cast
+#[derive(Copy, Clone, Debug)]
+pub struct CastHit {
+    /// Entity of the collider that was hit
+    pub entity: Entity,
+    /// World-space point of the hit
+    pub point: Vec2,
+    /// Normal of the hit surface, pointing towards the cast
+    pub normal: Vec2,
+    /// Distance along the cast direction to the hit
+    pub distance: f32,
+}
+
+/// Result of a proximity query
+#[derive(Copy, Clone, Debug)]
+pub struct ProximityHit {
+    /// Entity of the closest collider
+    pub entity: Entity,
+    /// Point of the collider, closest to the shape
+    pub point: Vec2,
+    /// Normalized direction from the collider to the shape
+    pub normal: Vec2,
+    /// Distance to the collider, or negative penetration depth,
+    /// if the shape overlaps it
+    pub signed_distance: f32,
+}
+
+/// System parameter to query the physics world.
+/// Only colliders, that interact with the given `layers`, are considered
+#[derive(SystemParam)]
+pub struct PhysicsQuery<'w, 's> {
+    collider_query: Query<'w, 's, (Entity, &'static Collider)>,
+}
+
+impl<'w, 's> PhysicsQuery<'w, 's> {
+    /// Colliders that interact with `layers`.
+    /// Attached colliders are a part of their parent's ones
+    fn colliders(
+        &self,
+        layers: CollisionLayers,
+    ) -> impl Iterator<Item = (Entity, &Collider)> {
+        self.collider_query.iter().filter(move |(_, collider)| {
+            !collider.attached && layers.interacts_with(&collider.layers)
+        })
+    }
+
+    /// Returns all colliders, that contain `point`
+    pub fn point_query(
+        &self,
+        point: Vec2,
+        layers: CollisionLayers,
+    ) -> Vec<Entity> {
+        self.colliders(layers)
+            .filter(|(_, collider)| collider.contains_point(point))
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    /// Returns all colliders, that intersect `bounding_box`
+    pub fn aabb_query(
+        &self,
+        bounding_box: &BoundingBox,
+        layers: CollisionLayers,
+    ) -> Vec<Entity> {
+        let (min, max) = (bounding_box.min, bounding_box.max);
+        let shape = Shape::Poly(PolyShape::new(vec![
+            min,
+            Vec2::new(max.x, min.y),
+            max,
+            Vec2::new(min.x, max.y),
+        ]));
+        self.overlap(&shape, &Transform::identity(), layers)
+    }
+
+    /// Returns all colliders, that overlap `shape`, placed with `transform`
+    pub fn overlap(
+        &self,
+        shape: &Shape,
+        transform: &Transform,
+        layers: CollisionLayers,
+    ) -> Vec<Entity> {
+        let mut overlapping =
+            Collider::nonsolid_from_shapes(vec![shape.clone()])
+                .with_layers(layers);
+        overlapping.update_shapes(transform);
+        self.colliders(layers)
+            .filter(|(_, collider)| {
+                collider.process_collision(&overlapping).is_some()
+            })
+            .map(|(entity, _)| entity)
+            .collect()
+    }
+
+    /// Returns the collider, closest to `shape`, placed with `transform`,
+    /// if it's within `max_dist`
+    pub fn nearest(
+        &self,
+        shape: &Shape,
+        transform: &Transform,
+        max_dist: f32,
+        layers: CollisionLayers,
+    ) -> Option<ProximityHit> {
+        let mut near = Collider::nonsolid_from_shapes(vec![shape.clone()])
+            .with_layers(layers);
+        near.update_shapes(transform);
+        let near_box = near.bounding_box().unwrap();
+        let margin = Vec2::splat(max_dist);
+        let search_box = BoundingBox::from_min_max(
+            near_box.min - margin,
+            near_box.max + margin,
+        );
+
+        self.colliders(layers)
+            .filter(|(_, collider)| {
+                matches!(collider.bounding_box(), Some(bounding_box)
+                    if bounding_box.collides(&search_box))
+            })
+            .filter_map(|(entity, collider)| {
+                let closest = collider.closest_points(&near)?;
+                Some(ProximityHit {
+                    entity,
+                    point: closest.point1,
+                    normal: closest.normal,
+                    signed_distance: closest.signed_distance,
+                })
+            })
+            .filter(|hit| hit.signed_distance <= max_dist)
+            .min_by(|hit1, hit2| {
+                hit1.signed_distance
+                    .partial_cmp(&hit2.signed_distance)
+                    .unwrap()
+            })
+    }
+
+    /// Returns the closest hit of the ray from `origin` along the nonzero
+    /// `dir` within `max_dist`
+    pub fn raycast(
+        &self,
+        origin: Vec2,
+        dir: Vec2,
+        max_dist: f32,
+        layers: CollisionLayers,
+    ) -> Option<CastHit> {
+        self.raycast_all(origin, dir, max_dist, layers)
+            .into_iter()
+            .next()
+    }
+
+    /// Returns hits of the ray from `origin` along the nonzero `dir`
+    /// within `max_dist` with all colliders, sorted by distance
+    pub fn raycast_all(
+        &self,
+        origin: Vec2,
+        dir: Vec2,
+        max_dist: f32,
+        layers: CollisionLayers,
+    ) -> Vec<CastHit> {
+        assert!(dir != Vec2::ZERO, "Zero raycast direction");
+        let dir = dir.normalize();
+        let mut hits: Vec<CastHit> = self
+            .colliders(layers)
+            .filter_map(|(entity, collider)| {
+                let (distance, normal) =
+                    collider.cast_ray(origin, dir, max_dist)?;
+                Some(CastHit {
+                    entity,
+                    point: origin + dir * distance,
+                    normal,
+                    distance,
+                })
+            })
+            .collect();
+        hits.sort_by(|hit1, hit2| {
+            hit1.distance.partial_cmp(&hit2.distance).unwrap()
+        });
+        hits
+    }
+
+    /// Moves `shape`, placed with `transform`, along the nonzero `dir` and
+    /// returns the first hit within `max_dist`, which must be finite
+    pub fn shape_cast(
+        &self,
+        shape: &Shape,
+        transform: &Transform,
+        dir: Vec2,
+        max_dist: f32,
+        layers: CollisionLayers,
+    ) -> Option<CastHit> {
+        assert!(
+            max_dist.is_finite() && max_dist >= 0.0,
+            "Invalid shape cast distance = {}",
+            max_dist
+        );
+        assert!(dir != Vec2::ZERO, "Zero shape cast direction");
+        let dir = dir.normalize();
+        let mut cast = Collider::nonsolid_from_shapes(vec![shape.clone()])
+            .with_layers(layers);
+
+        // The box, swept by the shape, used to skip far away colliders
+        let mut end_transform = *transform;
+        end_transform.translate(dir * max_dist);
+        cast.update_shapes(&end_transform);
+        let end_box = cast.bounding_box().unwrap().clone();
+        cast.update_shapes(transform);
+        let swept_box = cast.bounding_box().unwrap().merged(&end_box);
+
+        self.colliders(layers)
+            .filter(|(_, collider)| {
+                matches!(collider.bounding_box(), Some(bounding_box)
+                    if bounding_box.collides(&swept_box))
+            })
+            .filter_map(|(entity, collider)| {
+                let (distance, normal, contact) = cast_collider(
+                    &mut cast, transform, dir, max_dist, collider,
+                )?;
+                Some(CastHit {
+                    entity,
+                    point: contact,
+                    normal,
+                    distance,
+                })
+            })
+            .min_by(|hit1, hit2| {
+                hit1.distance.partial_cmp(&hit2.distance).unwrap()
+            })
+    }
+}
+
+/// Distance, within which a shape cast is considered to hit a collider
+const CAST_TOLERANCE: f32 = 1e-3;
+/// Maximum number of advancements of a shape cast towards a collider
+const MAX_CAST_ITERATIONS: usize = 32;
+
+/// Moves `cast` from `transform` along the normalized `dir` and returns
+/// `Some((distance, normal, contact))` of the first collision with `other`
+/// within `max_dist`, where `normal` points out of `other`.
+/// The cast is advanced by the distance, that it surely can move without
+/// hitting `other` (see [Collider::cast_bound]), until it touches `other`
+pub(super) fn cast_collider(
+    cast: &mut Collider,
+    transform: &Transform,
+    dir: Vec2,
+    max_dist: f32,
+    other: &Collider,
+) -> Option<(f32, Vec2, Vec2)> {
+    if !cast.layers.interacts_with(&other.layers) {
+        return None;
+    }
+    let mut distance = 0.0;
+    let mut hit = None;
+    for _ in 0..MAX_CAST_ITERATIONS {
+        let mut cast_transform = *transform;
+        cast_transform.translate(dir * distance);
+        cast.update_shapes(&cast_transform);
+        let (bound, closest) = cast.cast_bound(other, dir)?;
+        if distance + bound > max_dist {
+            return None;
+        }
+        distance += bound;
+        hit = Some((distance, -closest.normal, closest.point2));
+        if bound < CAST_TOLERANCE {
+            break;
+        }
+    }
+    // If the cast hasn't converged, it's reported a bit before the collider
+    hit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::physics::test_util::rect;
+
+    #[test]
+    fn cast_collider_test() {
+        // A wall, much thinner than the cast collider, far away
+        let mut wall = Collider::solid_from_shapes(vec![rect(
+            Vec2::ZERO,
+            Vec2::new(0.01, 5.0),
+        )]);
+        wall.update(&Transform::from_xyz(1000.0, 0.0, 0.0));
+        let mut cast =
+            Collider::solid_from_shapes(vec![rect(Vec2::ZERO, Vec2::ONE)]);
+        let transform = Transform::from_xyz(0.0, 1.0, 0.0);
+        let dir = Vec2::new(1.0, 0.003).normalize();
+
+        let (distance, normal, _) =
+            cast_collider(&mut cast, &transform, dir, 1e9, &wall).unwrap();
+        let expected = (1000.0 - 0.01 - 1.0) / dir.x;
+        assert!((distance - expected).abs() < 1e-2, "{}", distance);
+        assert!(normal.abs_diff_eq(-Vec2::X, 1e-3), "{}", normal);
+
+        // Not within the distance, or moving away
+        assert!(
+            cast_collider(&mut cast, &transform, dir, 900.0, &wall).is_none()
+        );
+        assert!(
+            cast_collider(&mut cast, &transform, -dir, 1e9, &wall).is_none()
+        );
+    }
+}
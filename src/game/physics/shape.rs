@@ -1,20 +1,132 @@
-//! 2D shapes: circles and convex polygons
+//! 2D shapes: circles, ellipses, capsules and convex polygons
 use std::f32::consts::PI;
 
-use bevy::math::Vec2;
+use bevy::math::{Mat2, Vec2};
 use bevy::transform::components::Transform;
 
-use crate::util::{iter, Vec2Ext};
+use crate::util::{iter, QuatExt, Vec2Ext};
 
-use super::util::{segments_intersection, update_max_point, update_min_point};
+use super::gjk;
+use super::util::{segments_push, update_max_point, update_min_point};
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "debug", derive(bevy_inspector_egui::Inspectable))]
 pub enum Shape {
     Circle(CircleShape),
+    Ellipse(EllipseShape),
+    Capsule(CapsuleShape),
     Poly(PolyShape),
 }
 
+impl Shape {
+    /// Area of the shape
+    pub fn area(&self) -> f32 {
+        match self {
+            Shape::Circle(circle) => PI * circle.radius * circle.radius,
+            Shape::Ellipse(ellipse) => PI * ellipse.radii.x * ellipse.radii.y,
+            Shape::Capsule(capsule) => {
+                let radius = capsule.radius;
+                2.0 * radius * capsule.length() + PI * radius * radius
+            }
+            Shape::Poly(poly) => iter::pairs(poly.points.iter())
+                .map(|(a, b)| a.perp_dot(*b) * 0.5)
+                .sum::<f32>()
+                .abs(),
+        }
+    }
+
+    /// Polar second moment of area about `pivot`.
+    /// Multiplied by `mass / area` gives the moment of inertia.
+    pub fn polar_moment(&self, pivot: Vec2) -> f32 {
+        match self {
+            Shape::Circle(circle) => {
+                let area = self.area();
+                // Own moment plus the parallel axis term
+                area * (circle.radius * circle.radius * 0.5
+                    + circle.center.distance_squared(pivot))
+            }
+            Shape::Ellipse(ellipse) => {
+                self.area()
+                    * (ellipse.radii.length_squared() * 0.25
+                        + ellipse.center.distance_squared(pivot))
+            }
+            Shape::Capsule(capsule) => {
+                let radius = capsule.radius;
+                let half_length = capsule.length() * 0.5;
+                // The rectangle between the ends about the center
+                let rect_area = 4.0 * radius * half_length;
+                let rect_moment = rect_area
+                    * (half_length * half_length + radius * radius)
+                    / 3.0;
+                // Both half disk ends about the center
+                let ends_area = PI * radius * radius;
+                let ends_moment = ends_area
+                    * (radius * radius * 0.5 + half_length * half_length)
+                    + 8.0 / 3.0 * half_length * radius.powi(3);
+                rect_moment
+                    + ends_moment
+                    + self.area() * capsule.center().distance_squared(pivot)
+            }
+            Shape::Poly(poly) => {
+                // Sum over triangles (pivot, a, b)
+                iter::pairs(poly.points.iter())
+                    .map(|(&a, &b)| {
+                        let (a, b) = (a - pivot, b - pivot);
+                        a.perp_dot(b) * (a.dot(a) + a.dot(b) + b.dot(b)) / 12.0
+                    })
+                    .sum::<f32>()
+                    .abs()
+            }
+        }
+    }
+
+    /// Returns the point of the shape, that is furthest along `dir`.
+    /// If there are several such points (e.g. an edge perpendicular to `dir`),
+    /// their midpoint is returned.
+    pub fn support(&self, dir: Vec2) -> Vec2 {
+        const EPS: f32 = 1e-3;
+        match self {
+            Shape::Circle(circle) => {
+                circle.center + dir.normalize_or_zero() * circle.radius
+            }
+            Shape::Ellipse(ellipse) => {
+                // Support of the unit circle, mapped onto the ellipse
+                let map = ellipse.linear_map();
+                let local_dir = map.transpose() * dir;
+                ellipse.center + map * local_dir.normalize_or_zero()
+            }
+            Shape::Capsule(capsule) => {
+                let dir = dir.normalize_or_zero();
+                let proj = (capsule.b - capsule.a).dot(dir);
+                let end = if proj > EPS {
+                    capsule.b
+                } else if proj < -EPS {
+                    capsule.a
+                } else {
+                    capsule.center()
+                };
+                end + dir * capsule.radius
+            }
+            Shape::Poly(poly) => {
+                let dir = dir.normalize_or_zero();
+                let max_proj = poly
+                    .points
+                    .iter()
+                    .map(|point| point.dot(dir))
+                    .fold(f32::NEG_INFINITY, f32::max);
+                let (sum, count) = poly
+                    .points
+                    .iter()
+                    .filter(|point| point.dot(dir) >= max_proj - EPS)
+                    .fold((Vec2::ZERO, 0.0), |(sum, count), &point| {
+                        (sum + point, count + 1.0)
+                    });
+                sum / count
+            }
+        }
+    }
+}
+
 // TODO probably should remove this Default, it's only here for the inspector
 impl Default for Shape {
     fn default() -> Self {
@@ -27,18 +139,88 @@ impl Default for Shape {
 pub struct CircleShape {
     pub radius: f32,
     pub center: Vec2,
-    /// Current normal, used internally
-    normal: Vec2,
 }
 
 impl CircleShape {
     pub fn new(radius: f32, center: Vec2) -> Self {
+        Self { radius, center }
+    }
+}
+
+/// Ellipse with semi-axes `radii`,
+/// rotated counter-clockwise by `rotation` radians
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "debug", derive(bevy_inspector_egui::Inspectable))]
+pub struct EllipseShape {
+    pub radii: Vec2,
+    pub center: Vec2,
+    pub rotation: f32,
+}
+
+impl EllipseShape {
+    pub fn new(radii: Vec2, center: Vec2, rotation: f32) -> Self {
         Self {
-            radius,
+            radii,
             center,
-            normal: Vec2::default(),
+            rotation,
         }
     }
+
+    /// Linear map from the unit circle onto the ellipse
+    fn linear_map(&self) -> Mat2 {
+        Mat2::from_angle(self.rotation) * Mat2::from_diagonal(self.radii)
+    }
+
+    /// The image of the unit circle under `map`, moved to `center`.
+    /// Uses the closed form singular value decomposition
+    /// `map = rotation(phi) * diagonal(radii) * rotation(theta)`,
+    /// where the last rotation doesn't change the unit circle
+    fn from_linear_map(center: Vec2, map: Mat2) -> Self {
+        let (m00, m10) = (map.x_axis.x, map.x_axis.y);
+        let (m01, m11) = (map.y_axis.x, map.y_axis.y);
+        let (e, f) = ((m00 + m11) * 0.5, (m00 - m11) * 0.5);
+        let (g, h) = ((m10 + m01) * 0.5, (m10 - m01) * 0.5);
+        let q = (e * e + h * h).sqrt();
+        let r = (f * f + g * g).sqrt();
+        let phi = (h.atan2(e) + g.atan2(f)) * 0.5;
+        Self::new(Vec2::new(q + r, (q - r).abs()), center, phi)
+    }
+}
+
+/// Segment from `a` to `b`, extended by `radius` in all directions
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "debug", derive(bevy_inspector_egui::Inspectable))]
+pub struct CapsuleShape {
+    pub a: Vec2,
+    pub b: Vec2,
+    pub radius: f32,
+}
+
+impl CapsuleShape {
+    pub fn new(a: Vec2, b: Vec2, radius: f32) -> Self {
+        Self { a, b, radius }
+    }
+
+    /// Length of the segment between the centers of the ends
+    #[inline]
+    pub fn length(&self) -> f32 {
+        self.a.distance(self.b)
+    }
+
+    #[inline]
+    pub fn center(&self) -> Vec2 {
+        (self.a + self.b) * 0.5
+    }
+
+    /// Point of the segment from `a` to `b`, that is closest to `point`
+    fn closest_segment_point(&self, point: Vec2) -> Vec2 {
+        let axis = self.b - self.a;
+        if axis == Vec2::ZERO {
+            return self.a;
+        }
+        let t = (point - self.a).dot(axis) / axis.length_squared();
+        self.a + axis * t.clamp(0.0, 1.0)
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -122,42 +304,195 @@ impl ShiftedShape {
         }
     }
 
+    /// Current (transformed) shape
+    #[inline]
+    pub fn shape(&self) -> &Shape {
+        &self.shape
+    }
+
+    /// Returns the point of the shape, that is furthest along `dir`.
+    /// If there are several such points (e.g. an edge perpendicular to `dir`),
+    /// their midpoint is returned.
+    #[inline]
+    pub fn support(&self, dir: Vec2) -> Vec2 {
+        self.shape.support(dir)
+    }
+
+    /// Returns whether `point` is inside of the shape
+    pub fn contains_point(&self, point: Vec2) -> bool {
+        match &self.shape {
+            Shape::Circle(circle) => {
+                circle.center.distance_squared(point)
+                    <= circle.radius * circle.radius
+            }
+            Shape::Ellipse(ellipse) => {
+                let local_point =
+                    ellipse.linear_map().inverse() * (point - ellipse.center);
+                local_point.length_squared() <= 1.0
+            }
+            Shape::Capsule(capsule) => {
+                capsule.closest_segment_point(point).distance_squared(point)
+                    <= capsule.radius * capsule.radius
+            }
+            Shape::Poly(poly) => {
+                poly.points.iter().zip(poly.normals.iter()).all(
+                    |(&edge_point, normal)| {
+                        normal.dot(point - edge_point) <= 0.0
+                    },
+                )
+            }
+        }
+    }
+
+    /// Returns `Some((distance, normal))` if the ray from `origin` along
+    /// the normalized `dir` hits the shape within `max_dist`, and `None` otherwise.
+    /// A ray, starting inside the shape, hits it at distance 0 with normal `-dir`
+    pub fn cast_ray(
+        &self,
+        origin: Vec2,
+        dir: Vec2,
+        max_dist: f32,
+    ) -> Option<(f32, Vec2)> {
+        match &self.shape {
+            Shape::Circle(circle) => cast_ray_circle(
+                circle.center,
+                circle.radius,
+                origin,
+                dir,
+                max_dist,
+            ),
+            Shape::Ellipse(ellipse) => {
+                // Cast the ray at the unit circle instead
+                let inverse = ellipse.linear_map().inverse();
+                let local_dir = inverse * dir;
+                // Distances are scaled by the length of `local_dir`
+                let scale = local_dir.length();
+                let (dist, local_normal) = cast_ray_circle(
+                    Vec2::ZERO,
+                    1.0,
+                    inverse * (origin - ellipse.center),
+                    local_dir / scale,
+                    max_dist * scale,
+                )?;
+                if dist == 0.0 {
+                    return Some((0.0, -dir));
+                }
+                // Normals are mapped with the inverse transpose
+                let normal = (inverse.transpose() * local_normal).normalize();
+                Some((dist / scale, normal))
+            }
+            Shape::Capsule(capsule) => {
+                // The capsule is the union of the circles at its ends
+                // and the rectangle between them
+                let axis = (capsule.b - capsule.a).normalize_or_zero();
+                let side = axis.perp();
+                let rect_hit = (axis != Vec2::ZERO)
+                    .then(|| {
+                        let planes = [
+                            (capsule.a + side * capsule.radius, side),
+                            (capsule.a - side * capsule.radius, -side),
+                            (capsule.a, -axis),
+                            (capsule.b, axis),
+                        ];
+                        cast_ray_half_planes(
+                            planes.into_iter(),
+                            origin,
+                            dir,
+                            max_dist,
+                        )
+                    })
+                    .flatten();
+                [
+                    cast_ray_circle(
+                        capsule.a,
+                        capsule.radius,
+                        origin,
+                        dir,
+                        max_dist,
+                    ),
+                    cast_ray_circle(
+                        capsule.b,
+                        capsule.radius,
+                        origin,
+                        dir,
+                        max_dist,
+                    ),
+                    rect_hit,
+                ]
+                .into_iter()
+                .flatten()
+                .min_by(|(dist1, _), (dist2, _)| {
+                    dist1.partial_cmp(dist2).unwrap()
+                })
+            }
+            Shape::Poly(poly) => cast_ray_half_planes(
+                poly.points
+                    .iter()
+                    .copied()
+                    .zip(poly.normals.iter().copied()),
+                origin,
+                dir,
+                max_dist,
+            ),
+        }
+    }
+
     /// Updates the shape with `transform`. Returns min and max points of the new shape.
     pub fn update(&mut self, transform: &Transform) -> (Vec2, Vec2) {
-        // Bottom left of the bounding box
-        let mut min_point: Vec2 = Vec2::new(f32::INFINITY, f32::INFINITY);
-        // Top right of the bounding box
-        let mut max_point: Vec2 =
-            Vec2::new(f32::NEG_INFINITY, f32::NEG_INFINITY);
-        match (&self.original_shape, &mut self.shape) {
-            (
-                Shape::Circle(original_circle_shape),
-                Shape::Circle(circle_shape),
-            ) => {
-                assert!(
-                    transform.scale.x == transform.scale.y,
-                    "Scaling a circle into\\
-                    an ellipse is not implemented yet"
-                );
-                circle_shape.radius =
-                    original_circle_shape.radius * transform.scale.x;
-                circle_shape.center =
+        let scale = transform.scale.truncate();
+        // Linear part of the transform
+        let linear_map = Mat2::from_angle(transform.rotation.to_angle())
+            * Mat2::from_diagonal(scale);
+        match &self.original_shape {
+            Shape::Circle(original_circle_shape) => {
+                let center =
                     original_circle_shape.center.apply_transform(transform);
-                update_min_point(
-                    &mut min_point,
-                    circle_shape.center - Vec2::splat(circle_shape.radius),
-                );
-                update_max_point(
-                    &mut max_point,
-                    circle_shape.center + Vec2::splat(circle_shape.radius),
-                );
+                let radius = original_circle_shape.radius;
+                self.shape = if scale.x.abs() == scale.y.abs() {
+                    Shape::Circle(CircleShape::new(
+                        radius * scale.x.abs(),
+                        center,
+                    ))
+                } else {
+                    // A non-uniformly scaled circle is an ellipse
+                    Shape::Ellipse(EllipseShape::from_linear_map(
+                        center,
+                        linear_map * radius,
+                    ))
+                };
             }
-            (Shape::Poly(original_poly_shape), Shape::Poly(poly_shape)) => {
+            Shape::Ellipse(original_ellipse_shape) => {
+                self.shape = Shape::Ellipse(EllipseShape::from_linear_map(
+                    original_ellipse_shape.center.apply_transform(transform),
+                    linear_map * original_ellipse_shape.linear_map(),
+                ));
+            }
+            Shape::Capsule(original_capsule_shape) => {
+                // The ends have to stay round,
+                // so the radius is scaled by the smaller factor
+                self.shape = Shape::Capsule(CapsuleShape::new(
+                    original_capsule_shape.a.apply_transform(transform),
+                    original_capsule_shape.b.apply_transform(transform),
+                    original_capsule_shape.radius
+                        * f32::min(scale.x.abs(), scale.y.abs()),
+                ));
+            }
+            Shape::Poly(original_poly_shape) => {
+                let poly_shape = match &mut self.shape {
+                    Shape::Poly(poly_shape) => poly_shape,
+                    _ => unreachable!(),
+                };
                 assert!(
-                    original_poly_shape.points.len() != 0
+                    !original_poly_shape.points.is_empty()
                         && original_poly_shape.points.len()
                             == poly_shape.points.len()
                 );
+                // Bottom left of the bounding box
+                let mut min_point: Vec2 =
+                    Vec2::new(f32::INFINITY, f32::INFINITY);
+                // Top right of the bounding box
+                let mut max_point: Vec2 =
+                    Vec2::new(f32::NEG_INFINITY, f32::NEG_INFINITY);
                 for (original_point, point) in original_poly_shape
                     .points
                     .iter()
@@ -171,10 +506,19 @@ impl ShiftedShape {
                 // Update the edges and normals of the poly shape
                 poly_shape.update_edges();
                 poly_shape.update_normals();
+                return (min_point, max_point);
             }
-
-            _ => unreachable!(),
         }
+
+        // The bounding box of a curved shape touches its supports along the axes
+        let min_point = Vec2::new(
+            self.shape.support(-Vec2::X).x,
+            self.shape.support(-Vec2::Y).y,
+        );
+        let max_point = Vec2::new(
+            self.shape.support(Vec2::X).x,
+            self.shape.support(Vec2::Y).y,
+        );
         (min_point, max_point)
     }
 
@@ -188,23 +532,23 @@ impl ShiftedShape {
         normals_buf: &mut Vec<Vec2>,
     ) {
         normals_buf.clear();
-        match &self.shape {
-            Shape::Circle(self_circle) => match &other_shape.shape {
-                Shape::Circle(other_circle) => {
-                    let normal =
-                        (other_circle.center - self_circle.center).normalize();
-                    normals_buf.push(normal);
-                }
-                Shape::Poly(other_poly) => {
-                    for &point in &other_poly.points {
-                        normals_buf
-                            .push((point - self_circle.center).normalize());
-                    }
+        match (&self.shape, &other_shape.shape) {
+            (Shape::Circle(self_circle), Shape::Circle(other_circle)) => {
+                let normal =
+                    (other_circle.center - self_circle.center).normalize();
+                normals_buf.push(normal);
+            }
+            (Shape::Circle(self_circle), Shape::Poly(other_poly)) => {
+                for &point in &other_poly.points {
+                    normals_buf.push((point - self_circle.center).normalize());
                 }
-            },
-            Shape::Poly(self_poly) => {
+            }
+            (Shape::Poly(self_poly), _) => {
                 normals_buf.extend_from_slice(&self_poly.normals)
             }
+            _ => {
+                unreachable!("Only circles and polygons are collided with SAT")
+            }
         }
     }
 
@@ -224,8 +568,8 @@ impl ShiftedShape {
                 )
             }
             Shape::Poly(poly) => {
-                let mut min = std::f32::INFINITY;
-                let mut max = std::f32::NEG_INFINITY;
+                let mut min = f32::INFINITY;
+                let mut max = f32::NEG_INFINITY;
                 for &point in &poly.points {
                     let proj = point.dot(normal);
                     if proj < min {
@@ -237,65 +581,293 @@ impl ShiftedShape {
                 }
                 (min, max)
             }
+            shape => (
+                shape.support(-normal).dot(normal),
+                shape.support(normal).dot(normal),
+            ),
         }
     }
 
     #[inline]
-    fn update_mpv(cur_mpv: &mut Vec2, mpv_dir: Vec2, mpv_len: f32) {
-        if mpv_len * mpv_len < cur_mpv.length_squared() {
-            *cur_mpv = mpv_dir * mpv_len;
+    fn update_mpv(cur_mpv: &mut Vec2, normal: Vec2, push: f32) {
+        if push * push < cur_mpv.length_squared() {
+            *cur_mpv = normal * push;
         }
     }
 
     /// Returns `Some(mpv) if two shapes are colliding and `None` otherwise,
     /// where `mpv` is the Minimum Push Vector to push `other` out of `self`
+    pub(super) fn process_collision(
+        &self,
+        other: &ShiftedShape,
+        normal_buf: &mut Vec<Vec2>,
+    ) -> Option<Vec2> {
+        match (&self.shape, &other.shape) {
+            (
+                Shape::Circle(_) | Shape::Poly(_),
+                Shape::Circle(_) | Shape::Poly(_),
+            ) => self.process_sat_collision(other, normal_buf),
+            // Ellipses and capsules don't have a finite set of separating axes
+            _ => {
+                let closest = gjk::closest_points(&self.shape, &other.shape);
+                (closest.signed_distance < 0.0)
+                    .then(|| closest.normal * -closest.signed_distance)
+            }
+        }
+    }
+
+    /// [Self::process_collision] using the Separating Axis Theorem
     ///
     /// TODO: maybe optimize the Circle to Circle case
-    pub(super) fn process_collision(
+    fn process_sat_collision(
         &self,
         other: &ShiftedShape,
         normal_buf: &mut Vec<Vec2>,
     ) -> Option<Vec2> {
-        let mut mpv = Vec2::new(std::f32::INFINITY, std::f32::INFINITY);
+        let mut mpv = Vec2::new(f32::INFINITY, f32::INFINITY);
 
-        // Iterate over collision normals
+        // Iterate over collision normals of both shapes
         let (shape1, shape2) = (self, other);
-        shape1.get_normals(shape2, normal_buf);
-        for normal in normal_buf.iter() {
-            // Project both shapes onto the normal
-            let seg1 = shape1.project(*normal);
-            let seg2 = shape2.project(*normal);
-            // Determine if `seg1` and `seg2` intersect
-            let intersection = segments_intersection(seg1, seg2);
-            if let Some(intersection_len) = intersection {
-                // Update the mpv
-                Self::update_mpv(&mut mpv, *normal, intersection_len);
-            } else {
-                // No intersection on a projection means these shapes are not colliding
-                return None;
+        for (from, to) in [(shape1, shape2), (shape2, shape1)] {
+            from.get_normals(to, normal_buf);
+            for normal in normal_buf.iter() {
+                // Project both shapes onto the normal
+                let seg1 = shape1.project(*normal);
+                let seg2 = shape2.project(*normal);
+                // Determine how far `seg2` has to be pushed out of `seg1`
+                if let Some(push) = segments_push(seg1, seg2) {
+                    // Update the mpv
+                    Self::update_mpv(&mut mpv, *normal, push);
+                } else {
+                    // No intersection on a projection means these shapes are not colliding
+                    return None;
+                }
             }
         }
 
-        // Do the same for the normals of the second shape
-        // Iterate over collision normals
-        shape2.get_normals(shape1, normal_buf);
-        for normal in normal_buf.iter() {
-            // Project both shapes onto the normal
-            let seg1 = shape1.project(*normal);
-            let seg2 = shape2.project(*normal);
-            // Determine if `seg1` and `seg2` intersect
-            let intersection = segments_intersection(seg1, seg2);
-            if let Some(intersection_len) = intersection {
-                // Note that we invert the direction of the normal
-                Self::update_mpv(&mut mpv, -*normal, intersection_len);
-            } else {
-                // No intersection on a projection means these shapes are not colliding
+        // If we got here, that means there is no seperating axis,
+        // so the shapes are collidding
+        Some(mpv)
+    }
+}
+
+/// Returns `Some((distance, normal))` if the ray from `origin` along
+/// the normalized `dir` hits the circle within `max_dist`
+fn cast_ray_circle(
+    center: Vec2,
+    radius: f32,
+    origin: Vec2,
+    dir: Vec2,
+    max_dist: f32,
+) -> Option<(f32, Vec2)> {
+    let to_origin = origin - center;
+    let c = to_origin.length_squared() - radius.powi(2);
+    if c <= 0.0 {
+        return Some((0.0, -dir));
+    }
+    // Solve `|to_origin + dir * t| = radius` for t
+    let b = to_origin.dot(dir);
+    let discriminant = b * b - c;
+    if b > 0.0 || discriminant < 0.0 {
+        return None;
+    }
+    let dist = -b - discriminant.sqrt();
+    (dist <= max_dist).then(|| (dist, (to_origin + dir * dist) / radius))
+}
+
+/// Returns `Some((distance, normal))` if the ray from `origin` along
+/// the normalized `dir` hits the intersection of the half-planes,
+/// given as `(point on the boundary, outward normal)`, within `max_dist`
+fn cast_ray_half_planes(
+    planes: impl Iterator<Item = (Vec2, Vec2)>,
+    origin: Vec2,
+    dir: Vec2,
+    max_dist: f32,
+) -> Option<(f32, Vec2)> {
+    // Clip the ray with all half-planes
+    let (mut enter, mut exit) = (0.0, max_dist);
+    let mut normal = -dir;
+    for (point, plane_normal) in planes {
+        let dist_to_edge = plane_normal.dot(point - origin);
+        let speed = plane_normal.dot(dir);
+        if speed == 0.0 {
+            if dist_to_edge < 0.0 {
+                // Parallel to the edge and outside of it
                 return None;
             }
+            continue;
+        }
+        let dist = dist_to_edge / speed;
+        if speed < 0.0 {
+            if dist > enter {
+                enter = dist;
+                normal = plane_normal;
+            }
+        } else {
+            exit = f32::min(exit, dist);
+        }
+        if enter > exit {
+            return None;
         }
+    }
+    Some((enter, normal))
+}
 
-        // If we got here, that means there is no seperating axis,
-        // so the shapes are collidding
-        Some(mpv)
+#[cfg(test)]
+mod tests {
+    use bevy::math::{Quat, Vec3};
+
+    use super::super::test_util::square;
+    use super::*;
+
+    fn shifted(shape: Shape) -> ShiftedShape {
+        let mut shape = ShiftedShape::from_original_shape(shape);
+        shape.update(&Transform::identity());
+        shape
+    }
+
+    #[test]
+    fn mass_properties_test() {
+        const EPS: f32 = 1e-5;
+        let square = square(Vec2::ZERO, 1.0);
+        assert!((square.area() - 4.0).abs() < EPS);
+        assert!((square.polar_moment(Vec2::ZERO) - 8.0 / 3.0).abs() < EPS);
+
+        let circle = Shape::Circle(CircleShape::new(1.0, Vec2::new(1.0, 0.0)));
+        assert!((circle.area() - PI).abs() < EPS);
+        assert!((circle.polar_moment(Vec2::ZERO) - 1.5 * PI).abs() < EPS);
+    }
+
+    #[test]
+    fn mpv_direction_test() {
+        const EPS: f32 = 1e-5;
+        let mut normal_buf = Vec::new();
+        let left = shifted(square(Vec2::ZERO, 1.0));
+        let right = shifted(square(Vec2::new(1.5, 0.2), 1.0));
+        let mpv = left.process_collision(&right, &mut normal_buf).unwrap();
+        assert!(mpv.abs_diff_eq(Vec2::new(0.5, 0.0), EPS));
+        let mpv = right.process_collision(&left, &mut normal_buf).unwrap();
+        assert!(mpv.abs_diff_eq(Vec2::new(-0.5, 0.0), EPS));
+
+        // The support of an edge is its midpoint
+        assert!(left
+            .support(Vec2::new(1.0, 0.0))
+            .abs_diff_eq(Vec2::new(1.0, 0.0), EPS));
+    }
+
+    #[test]
+    fn cast_ray_test() {
+        const EPS: f32 = 1e-5;
+        let square = shifted(square(Vec2::ZERO, 1.0));
+        let (dist, normal) = square
+            .cast_ray(Vec2::new(-3.0, 0.5), Vec2::new(1.0, 0.0), 10.0)
+            .unwrap();
+        assert!((dist - 2.0).abs() < EPS);
+        assert!(normal.abs_diff_eq(Vec2::new(-1.0, 0.0), EPS));
+        // Too short, or passing by
+        assert!(square
+            .cast_ray(Vec2::new(-3.0, 0.5), Vec2::new(1.0, 0.0), 1.0)
+            .is_none());
+        assert!(square
+            .cast_ray(Vec2::new(-3.0, 1.5), Vec2::new(1.0, 0.0), 10.0)
+            .is_none());
+
+        let circle = shifted(Shape::Circle(CircleShape::new(1.0, Vec2::ZERO)));
+        let (dist, normal) = circle
+            .cast_ray(Vec2::new(0.0, 5.0), Vec2::new(0.0, -1.0), 10.0)
+            .unwrap();
+        assert!((dist - 4.0).abs() < EPS);
+        assert!(normal.abs_diff_eq(Vec2::new(0.0, 1.0), EPS));
+        // Starting inside
+        let (dist, _) = circle
+            .cast_ray(Vec2::ZERO, Vec2::new(0.0, -1.0), 10.0)
+            .unwrap();
+        assert_eq!(dist, 0.0);
+    }
+
+    #[test]
+    fn contains_point_test() {
+        let square = shifted(square(Vec2::ZERO, 1.0));
+        assert!(square.contains_point(Vec2::new(0.5, -0.9)));
+        assert!(!square.contains_point(Vec2::new(1.5, 0.0)));
+        let circle = shifted(Shape::Circle(CircleShape::new(1.0, Vec2::ONE)));
+        assert!(circle.contains_point(Vec2::new(1.5, 1.5)));
+        assert!(!circle.contains_point(Vec2::ZERO));
+    }
+
+    #[test]
+    fn scaled_circle_test() {
+        const EPS: f32 = 1e-4;
+        let mut circle = ShiftedShape::from_original_shape(Shape::Circle(
+            CircleShape::new(1.0, Vec2::ZERO),
+        ));
+        let transform =
+            Transform::from_rotation(Quat::from_rotation_z(PI / 2.0))
+                .with_scale(Vec3::new(2.0, 1.0, 1.0));
+        let (min, max) = circle.update(&transform);
+        assert!(min.abs_diff_eq(Vec2::new(-1.0, -2.0), EPS), "{}", min);
+        assert!(max.abs_diff_eq(Vec2::new(1.0, 2.0), EPS), "{}", max);
+        assert!((circle.shape().area() - 2.0 * PI).abs() < EPS);
+
+        assert!(circle.contains_point(Vec2::new(0.0, 1.9)));
+        assert!(!circle.contains_point(Vec2::new(1.5, 0.0)));
+        let (dist, normal) = circle
+            .cast_ray(Vec2::new(0.0, 5.0), Vec2::new(0.0, -1.0), 10.0)
+            .unwrap();
+        assert!((dist - 3.0).abs() < EPS);
+        assert!(normal.abs_diff_eq(Vec2::new(0.0, 1.0), EPS));
+        let (dist, normal) = circle
+            .cast_ray(Vec2::new(5.0, 0.0), Vec2::new(-1.0, 0.0), 10.0)
+            .unwrap();
+        assert!((dist - 4.0).abs() < EPS);
+        assert!(normal.abs_diff_eq(Vec2::new(1.0, 0.0), EPS));
+
+        // Collides with polygons through GJK
+        let mut normal_buf = Vec::new();
+        let square = shifted(square(Vec2::new(0.0, 2.5), 1.0));
+        let mpv = circle.process_collision(&square, &mut normal_buf).unwrap();
+        assert!(mpv.abs_diff_eq(Vec2::new(0.0, 0.5), 1e-3), "{}", mpv);
+    }
+
+    #[test]
+    fn capsule_test() {
+        const EPS: f32 = 1e-4;
+        let capsule = shifted(Shape::Capsule(CapsuleShape::new(
+            Vec2::new(-1.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            1.0,
+        )));
+        assert!((capsule.shape().area() - (4.0 + PI)).abs() < EPS);
+        // A capsule without length is a circle
+        let point =
+            Shape::Capsule(CapsuleShape::new(Vec2::ONE, Vec2::ONE, 1.0));
+        let circle = Shape::Circle(CircleShape::new(1.0, Vec2::ONE));
+        assert!(
+            (point.polar_moment(Vec2::ZERO) - circle.polar_moment(Vec2::ZERO))
+                .abs()
+                < EPS
+        );
+
+        assert!(capsule.contains_point(Vec2::new(1.5, 0.5)));
+        assert!(!capsule.contains_point(Vec2::new(0.0, 1.2)));
+        let (dist, normal) = capsule
+            .cast_ray(Vec2::new(0.0, 5.0), Vec2::new(0.0, -1.0), 10.0)
+            .unwrap();
+        assert!((dist - 4.0).abs() < EPS);
+        assert!(normal.abs_diff_eq(Vec2::new(0.0, 1.0), EPS));
+        let (dist, normal) = capsule
+            .cast_ray(Vec2::new(5.0, 0.0), Vec2::new(-1.0, 0.0), 10.0)
+            .unwrap();
+        assert!((dist - 3.0).abs() < EPS);
+        assert!(normal.abs_diff_eq(Vec2::new(1.0, 0.0), EPS));
+
+        let mut normal_buf = Vec::new();
+        let square = shifted(square(Vec2::new(2.5, 0.0), 1.0));
+        let mpv = capsule.process_collision(&square, &mut normal_buf).unwrap();
+        assert!(mpv.abs_diff_eq(Vec2::new(0.5, 0.0), 1e-3), "{}", mpv);
+        let circle =
+            shifted(Shape::Circle(CircleShape::new(1.0, Vec2::new(0.0, 1.5))));
+        let mpv = capsule.process_collision(&circle, &mut normal_buf).unwrap();
+        assert!(mpv.abs_diff_eq(Vec2::new(0.0, 0.5), 1e-3), "{}", mpv);
     }
 }
@@ -0,0 +1,332 @@
+//! Sequential impulse solver. All contacts and joints of a physics step
+//! are resolved together: impulses are applied to each contact in turn for several
+//! iterations, so that they converge to the ones that satisfy all contacts
+//! at once, and then the objects are pushed out of each other the same way.
+use bevy::math::Vec2;
+
+use super::joint::JointConstraint;
+use super::{DynamicObject, PhysicsConfig};
+
+/// Contact between a dynamic object and a static or another dynamic one
+#[derive(Clone, Debug)]
+pub(super) struct Contact {
+    /// Index of the first dynamic object
+    pub(super) body1: usize,
+    /// Index of the second dynamic object, `None` if it's static
+    pub(super) body2: Option<usize>,
+    /// Minimum Push Vector to push the first object out of the second one
+    pub(super) mpv: Vec2,
+    /// World-space point of contact
+    pub(super) point: Vec2,
+    /// Velocity of the surface of the static object at `point`
+    pub(super) surface_vel: Vec2,
+    /// Combined restitution of the materials
+    pub(super) restitution: f32,
+    /// Combined friction of the materials
+    pub(super) friction: f32,
+}
+
+/// [Contact], prepared for solving
+#[derive(Clone, Debug)]
+struct Constraint {
+    contact: Contact,
+    /// Normal, pointing from the second object to the first one
+    normal: Vec2,
+    /// Offset of `contact.point` from the origin of each object
+    offsets: (Vec2, Vec2),
+    /// Inverse of the effective mass along the normal and the tangent
+    inv_mass_normal: f32,
+    inv_mass_tangent: f32,
+    /// Relative normal velocity, that the contact should end up with
+    target_normal_vel: f32,
+    /// Impulses, accumulated over the iterations
+    normal_impulse: f32,
+    tangent_impulse: f32,
+}
+
+pub(super) struct Solver {
+    config: PhysicsConfig,
+    /// Copies of the dynamic objects, whose velocities are changed
+    bodies: Vec<DynamicObject>,
+    /// Translations of the objects before solving
+    translations: Vec<Vec2>,
+    /// Translations, by which the objects are pushed out of each other
+    corrections: Vec<Vec2>,
+    constraints: Vec<Constraint>,
+    joints: Vec<JointConstraint>,
+}
+
+impl Solver {
+    /// `bodies` are the dynamic objects with their translations,
+    /// that contacts refer to by their indices
+    pub(super) fn new(
+        bodies: Vec<(DynamicObject, Vec2)>,
+        config: &PhysicsConfig,
+    ) -> Self {
+        let corrections = vec![Vec2::ZERO; bodies.len()];
+        let (bodies, translations) = bodies.into_iter().unzip();
+        Self {
+            config: config.clone(),
+            bodies,
+            translations,
+            corrections,
+            constraints: Vec::new(),
+            joints: Vec::new(),
+        }
+    }
+
+    /// Speed, with which the objects of `contact` approach each other
+    pub(super) fn approach_speed(&self, contact: &Contact) -> f32 {
+        let normal = contact.mpv.normalize();
+        let offsets = self.offsets(contact);
+        (-self.relative_vel(contact, offsets).dot(normal)).max(0.0)
+    }
+
+    pub(super) fn add_contact(&mut self, contact: Contact) {
+        let normal = contact.mpv.normalize();
+        let tangent = normal.perp();
+        let offsets = self.offsets(&contact);
+        let inv_mass = |dir: Vec2| {
+            self.bodies[contact.body1].eff_inv_mass(offsets.0, dir)
+                + contact.body2.map_or(0.0, |body2| {
+                    self.bodies[body2].eff_inv_mass(offsets.1, dir)
+                })
+        };
+        let inv_mass_normal = inv_mass(normal);
+        let inv_mass_tangent = inv_mass(tangent);
+        // Bounce only off fast enough hits, so that resting contacts don't jitter
+        let normal_vel = self.relative_vel(&contact, offsets).dot(normal);
+        let target_normal_vel =
+            if normal_vel < -self.config.restitution_min_speed {
+                -contact.restitution * normal_vel
+            } else {
+                0.0
+            };
+        self.constraints.push(Constraint {
+            contact,
+            normal,
+            offsets,
+            inv_mass_normal,
+            inv_mass_tangent,
+            target_normal_vel,
+            normal_impulse: 0.0,
+            tangent_impulse: 0.0,
+        });
+    }
+
+    /// Adds `joint` and applies its forces over `delta` seconds
+    pub(super) fn add_joint(&mut self, joint: JointConstraint, delta: f32) {
+        joint.apply_force(&mut self.bodies, delta);
+        self.joints.push(joint);
+    }
+
+    /// Translation of the object at `index` before solving
+    pub(super) fn translation(&self, index: usize) -> Vec2 {
+        self.translations[index]
+    }
+
+    /// Whether the object at `index` has any contacts
+    pub(super) fn has_contacts(&self, index: usize) -> bool {
+        self.constraints.iter().any(|constraint| {
+            constraint.contact.body1 == index
+                || constraint.contact.body2 == Some(index)
+        })
+    }
+
+    /// Object at `index` with its velocities after solving
+    pub(super) fn body(&self, index: usize) -> &DynamicObject {
+        &self.bodies[index]
+    }
+
+    /// Translation, by which the object at `index` has to be pushed
+    pub(super) fn correction(&self, index: usize) -> Vec2 {
+        self.corrections[index]
+    }
+
+    /// Solves velocities and then positions of all joints and contacts
+    pub(super) fn solve(&mut self) {
+        for _ in 0..self.config.velocity_iterations {
+            for joint in &mut self.joints {
+                joint.solve_velocity(&mut self.bodies);
+            }
+            for index in 0..self.constraints.len() {
+                self.solve_velocity(index);
+            }
+        }
+        for _ in 0..self.config.position_iterations {
+            for joint in &self.joints {
+                joint.solve_position(
+                    &self.bodies,
+                    &self.translations,
+                    &mut self.corrections,
+                    self.config.position_correction,
+                );
+            }
+            for index in 0..self.constraints.len() {
+                self.solve_position(index);
+            }
+        }
+    }
+
+    /// Offsets of the contact point from the origins of both objects
+    fn offsets(&self, contact: &Contact) -> (Vec2, Vec2) {
+        let translation2 = contact
+            .body2
+            .map_or(contact.point, |body2| self.translations[body2]);
+        (
+            contact.point - self.translations[contact.body1],
+            contact.point - translation2,
+        )
+    }
+
+    /// Velocity of the first object relative to the second one
+    /// at the point of contact
+    fn relative_vel(&self, contact: &Contact, offsets: (Vec2, Vec2)) -> Vec2 {
+        let vel2 = match contact.body2 {
+            Some(body2) => self.bodies[body2].point_vel(offsets.1),
+            None => contact.surface_vel,
+        };
+        self.bodies[contact.body1].point_vel(offsets.0) - vel2
+    }
+
+    /// Applies `impulse` to the first object and the opposite one to the second
+    fn apply_impulse(&mut self, index: usize, impulse: Vec2) {
+        let constraint = &self.constraints[index];
+        let (body1, body2) =
+            (constraint.contact.body1, constraint.contact.body2);
+        let offsets = constraint.offsets;
+        self.bodies[body1].apply_impulse_at(impulse, offsets.0);
+        if let Some(body2) = body2 {
+            self.bodies[body2].apply_impulse_at(-impulse, offsets.1);
+        }
+    }
+
+    fn solve_velocity(&mut self, index: usize) {
+        let constraint = &self.constraints[index];
+        let (normal, tangent) = (constraint.normal, constraint.normal.perp());
+        let relative_vel =
+            self.relative_vel(&constraint.contact, constraint.offsets);
+
+        // Normal impulse, which can only push the objects apart
+        let delta = (constraint.target_normal_vel - relative_vel.dot(normal))
+            / constraint.inv_mass_normal;
+        let old_impulse = constraint.normal_impulse;
+        let new_impulse = (old_impulse + delta).max(0.0);
+        self.constraints[index].normal_impulse = new_impulse;
+        self.apply_impulse(index, normal * (new_impulse - old_impulse));
+
+        // Friction impulse along the surface, which can't exceed
+        // `friction` * `normal_impulse`
+        let constraint = &self.constraints[index];
+        let relative_vel =
+            self.relative_vel(&constraint.contact, constraint.offsets);
+        let max_impulse =
+            constraint.contact.friction * constraint.normal_impulse;
+        let delta = -relative_vel.dot(tangent) / constraint.inv_mass_tangent;
+        let old_impulse = constraint.tangent_impulse;
+        let new_impulse =
+            (old_impulse + delta).clamp(-max_impulse, max_impulse);
+        self.constraints[index].tangent_impulse = new_impulse;
+        self.apply_impulse(index, tangent * (new_impulse - old_impulse));
+    }
+
+    /// Pushes the objects apart along the normal, in proportion to their
+    /// inverse masses, leaving a small penetration for the contact to persist
+    fn solve_position(&mut self, index: usize) {
+        let constraint = &self.constraints[index];
+        let (body1, body2) =
+            (constraint.contact.body1, constraint.contact.body2);
+        let inv_mass1 = 1.0 / self.bodies[body1].mass;
+        let (inv_mass2, correction2) = match body2 {
+            Some(body2) => {
+                (1.0 / self.bodies[body2].mass, self.corrections[body2])
+            }
+            None => (0.0, Vec2::ZERO),
+        };
+        // Penetration, left after the corrections so far
+        let depth = constraint.contact.mpv.length()
+            - (self.corrections[body1] - correction2).dot(constraint.normal);
+        let slop = self.config.penetration_slop;
+        if depth <= slop {
+            return;
+        }
+        let push = constraint.normal
+            * ((depth - slop) * self.config.position_correction
+                / (inv_mass1 + inv_mass2));
+        self.corrections[body1] += push * inv_mass1;
+        if let Some(body2) = body2 {
+            self.corrections[body2] -= push * inv_mass2;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPS: f32 = 1e-4;
+
+    fn contact(
+        body1: usize,
+        body2: Option<usize>,
+        mpv: Vec2,
+        point: Vec2,
+        restitution: f32,
+    ) -> Contact {
+        Contact {
+            body1,
+            body2,
+            mpv,
+            point,
+            surface_vel: Vec2::ZERO,
+            restitution,
+            friction: 0.0,
+        }
+    }
+
+    #[test]
+    fn corner_test() {
+        // An object moving into the corner between two walls
+        let mut object = DynamicObject::new();
+        object.vel = Vec2::new(100.0, 100.0);
+        let mut solver =
+            Solver::new(vec![(object, Vec2::ZERO)], &PhysicsConfig::default());
+        for (mpv, point) in [
+            (Vec2::new(-1.0, 0.0), Vec2::new(1.0, 0.0)),
+            (Vec2::new(0.0, -1.0), Vec2::new(0.0, 1.0)),
+        ] {
+            solver.add_contact(contact(0, None, mpv, point, 1.0));
+        }
+        solver.solve();
+        // Bounced off both walls without gaining energy
+        let vel = solver.body(0).vel;
+        assert!(vel.abs_diff_eq(Vec2::new(-100.0, -100.0), EPS), "{}", vel);
+        let correction = solver.correction(0);
+        assert!(correction.x < -0.9 && correction.y < -0.9, "{}", correction);
+    }
+
+    #[test]
+    fn dynamic_contact_test() {
+        // Equal objects pushing into each other head-on stop
+        let mut object1 = DynamicObject::new();
+        object1.vel = Vec2::new(50.0, 0.0);
+        let mut object2 = DynamicObject::new();
+        object2.vel = Vec2::new(-50.0, 0.0);
+        let mut solver = Solver::new(
+            vec![
+                (object1, Vec2::new(-1.0, 0.0)),
+                (object2, Vec2::new(1.0, 0.0)),
+            ],
+            &PhysicsConfig::default(),
+        );
+        let mpv = Vec2::new(-1.0, 0.0);
+        solver.add_contact(contact(0, Some(1), mpv, Vec2::ZERO, 0.0));
+        solver.solve();
+        assert!(solver.body(0).vel.abs_diff_eq(Vec2::ZERO, EPS));
+        assert!(solver.body(1).vel.abs_diff_eq(Vec2::ZERO, EPS));
+        // And are pushed apart equally
+        let correction = solver.correction(0);
+        assert!((correction + solver.correction(1)).length() < EPS);
+        assert!(correction.x < -0.4, "{}", correction);
+    }
+}
@@ -0,0 +1,109 @@
+//! Surfaces are nonsolid colliders, which change how dynamic objects,
+//! overlapping them, move, e.g. ice, mud or boost pads.
+//! Which objects are affected is controlled with the collider's `layers`.
+//! Surfaces are found among the collisions of the previous physics step,
+//! so they take effect one step after an object enters or leaves them.
+use bevy::prelude::*;
+
+use super::{Collider, DynamicObject};
+
+/// Component, indicating that this entity's (nonsolid) collider is a surface
+#[derive(Component, Copy, Clone, Debug)]
+#[cfg_attr(feature = "debug", derive(bevy_inspector_egui::Inspectable))]
+pub struct Surface {
+    /// Overrides `friction_coeff` of the objects
+    pub friction_coeff: Option<f32>,
+    /// Multiplies the external acceleration of the objects
+    pub accel_scale: f32,
+    /// Overrides the maximum speed of the objects
+    pub max_vel: Option<f32>,
+}
+
+impl Default for Surface {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Surface {
+    /// Returns a surface, that doesn't change anything
+    pub fn new() -> Self {
+        Self {
+            friction_coeff: None,
+            accel_scale: 1.0,
+            max_vel: None,
+        }
+    }
+
+    /// Returns the surface with `friction_coeff` override set
+    pub fn with_friction_coeff(mut self, friction_coeff: f32) -> Self {
+        self.friction_coeff = Some(friction_coeff);
+        self
+    }
+
+    /// Returns the surface with `accel_scale` set
+    pub fn with_accel_scale(mut self, accel_scale: f32) -> Self {
+        self.accel_scale = accel_scale;
+        self
+    }
+
+    /// Returns the surface with `max_vel` override set
+    pub fn with_max_vel(mut self, max_vel: f32) -> Self {
+        assert!(max_vel >= 0.0, "Negative max_vel = {}", max_vel);
+        self.max_vel = Some(max_vel);
+        self
+    }
+}
+
+/// Movement parameters of a dynamic object, changed by the surfaces
+/// it overlaps
+#[derive(Copy, Clone, Debug)]
+pub(super) struct SurfaceParams {
+    pub friction_coeff: f32,
+    pub accel_scale: f32,
+    pub max_vel_squared: f32,
+}
+
+impl SurfaceParams {
+    /// Parameters of `dyn_object` with `collider`, which overlapped
+    /// the surfaces in its last step collisions, i.e. before it was moved
+    /// in the current step. Overrides of several surfaces are averaged,
+    /// acceleration scales are multiplied
+    pub fn new(
+        dyn_object: &DynamicObject,
+        collider: &Collider,
+        surface_query: &Query<&Surface>,
+    ) -> Self {
+        let mut params = Self {
+            friction_coeff: dyn_object.friction_coeff,
+            accel_scale: 1.0,
+            max_vel_squared: dyn_object.max_vel_squared,
+        };
+        // (Sum, count) of the overrides
+        let mut friction_coeffs = (0.0, 0);
+        let mut max_vels = (0.0, 0);
+        for surface in
+            collider.step_collisions().iter().filter_map(|collision| {
+                surface_query.get(collision.other_entity).ok()
+            })
+        {
+            params.accel_scale *= surface.accel_scale;
+            if let Some(friction_coeff) = surface.friction_coeff {
+                friction_coeffs.0 += friction_coeff;
+                friction_coeffs.1 += 1;
+            }
+            if let Some(max_vel) = surface.max_vel {
+                max_vels.0 += max_vel;
+                max_vels.1 += 1;
+            }
+        }
+        if friction_coeffs.1 > 0 {
+            params.friction_coeff =
+                friction_coeffs.0 / friction_coeffs.1 as f32;
+        }
+        if max_vels.1 > 0 {
+            params.max_vel_squared = (max_vels.0 / max_vels.1 as f32).powi(2);
+        }
+        params
+    }
+}
@@ -0,0 +1,19 @@
+//! Shapes, shared by the tests of the physics modules
+use bevy::math::Vec2;
+
+use super::shape::{PolyShape, Shape};
+
+/// Axis-aligned rectangle with `center` and `half_size`
+pub fn rect(center: Vec2, half_size: Vec2) -> Shape {
+    Shape::Poly(PolyShape::new(vec![
+        center + Vec2::new(-half_size.x, -half_size.y),
+        center + Vec2::new(half_size.x, -half_size.y),
+        center + Vec2::new(half_size.x, half_size.y),
+        center + Vec2::new(-half_size.x, half_size.y),
+    ]))
+}
+
+/// Axis-aligned square with `center` and `half_size`
+pub fn square(center: Vec2, half_size: f32) -> Shape {
+    rect(center, Vec2::splat(half_size))
+}
@@ -0,0 +1,130 @@
+use super::PhysicsConfig;
+
+/// Resource, that controls how fast the physics time passes
+/// relative to the real time.
+/// Everything, that is moved by the physics, is slowed down, frozen or
+/// stepped along with it
+#[derive(Clone, Debug)]
+pub struct PhysicsTime {
+    /// How many times the physics time is faster than the real time
+    pub time_scale: f32,
+    /// Whether the physics time doesn't pass (except for requested steps)
+    pub paused: bool,
+    /// Steps, requested with [PhysicsTime::step], that are not done yet
+    requested_steps: u32,
+    /// Physics time, that has passed, but wasn't stepped yet (in seconds)
+    accumulator: f32,
+    /// Steps, done in the last frame
+    pub(super) last_steps: u32,
+}
+
+impl Default for PhysicsTime {
+    fn default() -> Self {
+        Self {
+            time_scale: 1.0,
+            paused: false,
+            requested_steps: 0,
+            accumulator: 0.0,
+            last_steps: 0,
+        }
+    }
+}
+
+impl PhysicsTime {
+    /// Returns a physics time, that passes as fast as the real time
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the physics time with `time_scale` set
+    pub fn with_time_scale(mut self, time_scale: f32) -> Self {
+        self.time_scale = time_scale;
+        self
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Requests `steps` more steps of the physics, which are done while it's
+    /// paused, at most [PhysicsConfig::max_steps_per_frame] per frame,
+    /// and the rest of them in the next frames.
+    /// While it's running, the requested steps wait until it's paused
+    pub fn step(&mut self, steps: u32) {
+        self.requested_steps += steps;
+    }
+
+    /// Whether the physics is going to do any steps
+    pub fn is_running(&self) -> bool {
+        !self.paused || self.requested_steps > 0
+    }
+
+    /// Physics time, that passes during `real_delta` seconds of the real time
+    /// (not counting the requested steps)
+    pub fn scaled_delta(&self, real_delta: f32) -> f32 {
+        if self.paused {
+            0.0
+        } else {
+            real_delta * self.time_scale.max(0.0)
+        }
+    }
+
+    /// Lets `delta` seconds of the real time pass.
+    /// Returns the number of physics steps to do in this frame
+    pub(super) fn advance(
+        &mut self,
+        delta: f32,
+        config: &PhysicsConfig,
+    ) -> u32 {
+        let steps = if self.paused {
+            // Requested steps over the limit are done in the next frames
+            let steps = self.requested_steps.min(config.max_steps_per_frame);
+            self.requested_steps -= steps;
+            steps
+        } else {
+            self.accumulator += self.scaled_delta(delta);
+            let passed_steps = (self.accumulator / config.timestep) as u32;
+            self.accumulator -= passed_steps as f32 * config.timestep;
+            // Can't keep up, so slow down instead
+            passed_steps.min(config.max_steps_per_frame)
+        };
+        self.last_steps = steps;
+        steps
+    }
+
+    /// Part of the step, that has passed since the last one
+    pub(super) fn alpha(&self, config: &PhysicsConfig) -> f32 {
+        self.accumulator / config.timestep
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requested_steps_test() {
+        let config = PhysicsConfig::default();
+        let mut time = PhysicsTime::new();
+        time.pause();
+        time.step(config.max_steps_per_frame + 2);
+        assert_eq!(time.advance(1.0, &config), config.max_steps_per_frame);
+        assert_eq!(time.advance(1.0, &config), 2);
+        assert_eq!(time.advance(1.0, &config), 0);
+
+        // While running, only the passed time is stepped
+        time.step(1);
+        time.resume();
+        assert_eq!(time.advance(config.timestep * 1.5, &config), 1);
+        time.pause();
+        assert_eq!(time.advance(1.0, &config), 1);
+    }
+}
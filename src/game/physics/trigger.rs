@@ -0,0 +1,83 @@
+//! Triggers are nonsolid colliders, which report entities entering, staying
+//! inside and exiting them. Which entities are reported is controlled with
+//! the collider's `layers`.
+use bevy::prelude::*;
+
+use super::{
+    CollisionData, CollisionEnded, CollisionOngoing, CollisionStarted,
+};
+
+/// Component, indicating that this entity's (nonsolid) collider is a trigger
+#[derive(Component, Clone, Debug, Default)]
+pub struct Trigger {
+    /// Name of the trigger, used by gameplay code to tell triggers apart
+    pub name: String,
+}
+
+impl Trigger {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into() }
+    }
+}
+
+/// Event, sent once when `entity` enters `trigger`
+#[derive(Copy, Clone, Debug)]
+pub struct TriggerEntered {
+    pub trigger: Entity,
+    pub entity: Entity,
+}
+
+/// Event, sent every frame (except the first one) while `entity` is
+/// inside `trigger`
+#[derive(Copy, Clone, Debug)]
+pub struct TriggerStayed {
+    pub trigger: Entity,
+    pub entity: Entity,
+}
+
+/// Event, sent once when `entity` exits `trigger` (or is despawned)
+#[derive(Copy, Clone, Debug)]
+pub struct TriggerExited {
+    pub trigger: Entity,
+    pub entity: Entity,
+}
+
+/// Returns (trigger, entity) pairs for all triggers, involved in the collision
+fn trigger_pairs(
+    data: &CollisionData,
+    trigger_query: &Query<&Trigger>,
+) -> Vec<(Entity, Entity)> {
+    let (entity1, entity2) = data.entities;
+    [(entity1, entity2), (entity2, entity1)]
+        .into_iter()
+        .filter(|&(trigger, _)| trigger_query.get(trigger).is_ok())
+        .collect()
+}
+
+/// System, that sends trigger events, derived from collision events.
+/// Must run after `physics::send_collision_events`
+pub fn send_trigger_events(
+    trigger_query: Query<&Trigger>,
+    mut started_reader: EventReader<CollisionStarted>,
+    mut ongoing_reader: EventReader<CollisionOngoing>,
+    mut ended_reader: EventReader<CollisionEnded>,
+    mut entered_writer: EventWriter<TriggerEntered>,
+    mut stayed_writer: EventWriter<TriggerStayed>,
+    mut exited_writer: EventWriter<TriggerExited>,
+) {
+    for CollisionStarted(data) in started_reader.iter() {
+        for (trigger, entity) in trigger_pairs(data, &trigger_query) {
+            entered_writer.send(TriggerEntered { trigger, entity });
+        }
+    }
+    for CollisionOngoing(data) in ongoing_reader.iter() {
+        for (trigger, entity) in trigger_pairs(data, &trigger_query) {
+            stayed_writer.send(TriggerStayed { trigger, entity });
+        }
+    }
+    for CollisionEnded(data) in ended_reader.iter() {
+        for (trigger, entity) in trigger_pairs(data, &trigger_query) {
+            exited_writer.send(TriggerExited { trigger, entity });
+        }
+    }
+}
@@ -33,18 +33,21 @@ pub fn segments_intersect(a: (f32, f32), b: (f32, f32)) -> bool {
 }
 
 /// Tests whether segments `[a.0, a.1] and [b.0, b.1] intersect, and if they do
-/// returns the length of the intersection.
-///
-/// *Note*: works incorrectly if one segment contains another.
+/// returns the signed distance by which `b` has to be moved along the axis
+/// so that the segments only touch. The shortest of the two directions is chosen.
 #[inline]
-pub fn segments_intersection(a: (f32, f32), b: (f32, f32)) -> Option<f32> {
+pub fn segments_push(a: (f32, f32), b: (f32, f32)) -> Option<f32> {
     assert!(a.0 <= a.1 && b.0 <= b.1);
 
-    let intersection_len = if a.0 < b.0 { a.1 - b.0 } else { b.1 - a.0 };
-    if intersection_len >= 0.0 {
-        Some(intersection_len)
-    } else {
+    // Push `b` in the positive and in the negative direction respectively
+    let forward = a.1 - b.0;
+    let backward = b.1 - a.0;
+    if forward < 0.0 || backward < 0.0 {
         None
+    } else if forward < backward {
+        Some(forward)
+    } else {
+        Some(-backward)
     }
 }
 
@@ -61,4 +64,14 @@ mod tests {
         assert_eq!(segments_intersect((-2.0, -1.0), (-1.6, -1.6)), true);
         assert_eq!(segments_intersect((2.5, 3.5), (2.5, 2.6)), true);
     }
+
+    #[test]
+    fn segments_push_test() {
+        assert_eq!(segments_push((0.0, 1.0), (2.0, 3.0)), None);
+        assert_eq!(segments_push((0.0, 1.0), (0.5, 1.5)), Some(0.5));
+        assert_eq!(segments_push((0.5, 1.5), (0.0, 1.0)), Some(-0.5));
+        // One segment contains another
+        assert_eq!(segments_push((0.0, 4.0), (0.5, 1.0)), Some(-1.0));
+        assert_eq!(segments_push((0.0, 4.0), (2.5, 3.0)), Some(1.5));
+    }
 }
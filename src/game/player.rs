@@ -49,22 +49,17 @@ pub fn input(
     dynamic_object.accel = accel * config::physics::PLAYER_ACCEL;
 }
 
-/// Move player in response to player actions
-pub fn rotation(
-    time: Res<Time>,
-    mut player_query: Query<&mut Transform, With<Player>>,
-) {
-    // TODO add logging here
-    let mut transform = match player_query.get_single_mut() {
-        Ok(transform) => transform,
+/// Make the player constantly rotate. The rotation itself is applied in
+/// `physics::update`, so that it's blocked by walls like any other motion
+/// and follows [PhysicsTime](super::physics::PhysicsTime)
+pub fn rotation(mut player_query: Query<&mut DynamicObject, With<Player>>) {
+    let mut dynamic_object = match player_query.get_single_mut() {
+        Ok(dynamic_object) => dynamic_object,
         Err(QuerySingleError::NoEntities(_)) => return,
         Err(QuerySingleError::MultipleEntities(e)) => {
             panic!("Multiple player entities: {}", e);
         }
     };
 
-    QuatExt::rotate(
-        &mut transform.rotation,
-        time.delta_seconds() * ROTATION_SPEED,
-    );
+    dynamic_object.spin = ROTATION_SPEED;
 }
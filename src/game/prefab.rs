@@ -1,13 +1,23 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
+use itertools::Itertools;
 
+use crate::asset::svgdata::DataLabel;
 use crate::asset::{self, ImageHandles};
 use crate::asset::{svgdata, SvgData, SvgDataHandles};
 use crate::config;
+use crate::config::physics::layers;
 use crate::config::{depths, sizes};
-use crate::util::TransformExt;
+use crate::util::{TransformExt, Vec2Ext};
 
 use super::physics;
-use super::physics::{Collider, DynamicObject};
+use super::physics::shape::Shape;
+use super::physics::{
+    Collider, CollisionLayers, DynamicObject, ForceField, Interpolation,
+    KinematicBody, Surface, Trigger,
+};
+use super::physics::{Easing, PathFollower, PathMode};
 use super::player;
 
 // Resource, stat stores all prefabs
@@ -15,6 +25,10 @@ pub struct Prefabs {
     pub player: Player,
     pub princess: Princess,
     pub world_map1: WorldMap1,
+    pub world_map1_triggers: Vec<TriggerZone>,
+    pub world_map1_obstacles: Vec<Obstacle>,
+    pub world_map1_force_zones: Vec<ForceZone>,
+    pub world_map1_surface_zones: Vec<SurfaceZone>,
 }
 
 #[derive(Bundle, Clone)]
@@ -26,6 +40,7 @@ pub struct Player {
     pub player: player::Player,
     pub dynamic_object: DynamicObject,
     pub collider: Collider,
+    pub interpolation: Interpolation,
 }
 
 #[derive(Bundle, Clone)]
@@ -45,6 +60,209 @@ pub struct WorldMap1 {
     pub name: Name,
 }
 
+/// Invisible zone, that reports the player entering and exiting it
+#[derive(Bundle, Clone)]
+pub struct TriggerZone {
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+
+    pub name: Name,
+    pub collider: Collider,
+    pub trigger: Trigger,
+}
+
+/// Parsed labels and shapes of the groups of `svg_data`, whose labels start
+/// with `prefix`, sorted by label.
+/// Objects, built from the groups, are placed with the transform
+/// of the object, that `svg_data` belongs to
+fn labeled_groups<'a>(
+    svg_data: &'a SvgData,
+    prefix: &'a str,
+) -> impl Iterator<Item = (DataLabel<'a>, &'a [Shape])> {
+    svg_data
+        .groups
+        .iter()
+        .sorted_by_key(|(label, _)| *label)
+        .filter_map(move |(label, shapes)| {
+            let label = DataLabel::parse(label.strip_prefix(prefix)?);
+            Some((label, shapes.as_slice()))
+        })
+}
+
+/// Trigger zones, defined by `trigger:<name>` groups of `svg_data`
+fn trigger_zones(svg_data: &SvgData, transform: Transform) -> Vec<TriggerZone> {
+    labeled_groups(svg_data, svgdata::TRIGGER_PREFIX)
+        .map(|(label, shapes)| TriggerZone {
+            transform,
+            global_transform: GlobalTransform::from(transform),
+            name: Name::new(format!("Trigger {}", label.name)),
+            collider: Collider::nonsolid_from_shapes(shapes.to_vec())
+                .with_layers(CollisionLayers::new(
+                    layers::TRIGGER,
+                    layers::PLAYER,
+                )),
+            trigger: Trigger::new(label.name),
+        })
+        .collect()
+}
+
+/// Invisible solid obstacle, that moves along a path
+#[derive(Bundle, Clone)]
+pub struct Obstacle {
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+
+    pub name: Name,
+    pub collider: Collider,
+    pub kinematic_body: KinematicBody,
+    pub path_follower: PathFollower,
+    pub interpolation: Interpolation,
+}
+
+/// Obstacles, defined by `obstacle:<name>` groups of `svg_data`, which follow
+/// the paths `path:<name> mode=<mode> speed=<speed> easing=<easing>`
+fn obstacles(svg_data: &SvgData, transform: Transform) -> Vec<Obstacle> {
+    // Labels and points of the paths by their names
+    let paths: HashMap<&str, (DataLabel, &Vec<Vec2>)> = svg_data
+        .paths
+        .iter()
+        .map(|(label, points)| {
+            let label = DataLabel::parse(
+                label.strip_prefix(svgdata::PATH_PREFIX).unwrap(),
+            );
+            (label.name, (label, points))
+        })
+        .collect();
+
+    labeled_groups(svg_data, svgdata::OBSTACLE_PREFIX)
+        .map(|(label, shapes)| {
+            let name = label.name;
+            let (path_label, points) = paths
+                .get(name)
+                .unwrap_or_else(|| panic!("No path for obstacle {}", name));
+            let points: Vec<Vec2> = points
+                .iter()
+                .map(|point| point.apply_transform(&transform))
+                .collect();
+            let path_follower = PathFollower::new(
+                &points,
+                transform.translation.truncate(),
+                path_label.param("mode").unwrap_or(PathMode::Loop),
+                path_label
+                    .param("speed")
+                    .unwrap_or(config::physics::OBSTACLE_SPEED),
+            )
+            .with_easing(path_label.param("easing").unwrap_or(Easing::Linear));
+            Obstacle {
+                transform,
+                global_transform: GlobalTransform::from(transform),
+                name: Name::new(format!("Obstacle {}", name)),
+                collider: Collider::solid_from_shapes(shapes.to_vec()),
+                kinematic_body: KinematicBody::new(),
+                path_follower,
+                interpolation: Interpolation::new(),
+            }
+        })
+        .collect()
+}
+
+/// Invisible zone, that accelerates dynamic objects inside of it
+#[derive(Bundle, Clone)]
+pub struct ForceZone {
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+
+    pub name: Name,
+    pub collider: Collider,
+    pub force_field: ForceField,
+}
+
+/// Force zones, defined by `force:<name> kind=<kind> strength=<strength>
+/// angle=<angle> speed=<speed>` groups of `svg_data`, where `kind` is one of
+/// - `uniform`: acceleration `strength` in the direction `angle`
+/// - `radial`: acceleration `strength` towards the center of the zone
+/// - `vortex`: acceleration `strength` around the center of the zone
+/// - `current`: drag with coefficient `strength` towards the velocity
+///   `speed` in the direction `angle`
+///
+/// `angle` is in degrees counter-clockwise from the x axis
+fn force_zones(svg_data: &SvgData, transform: Transform) -> Vec<ForceZone> {
+    labeled_groups(svg_data, svgdata::FORCE_PREFIX)
+        .map(|(label, shapes)| {
+            let strength: f32 = label.param("strength").unwrap_or(0.0);
+            let angle: f32 = label.param("angle").unwrap_or(0.0);
+            let dir =
+                Vec2::new(angle.to_radians().cos(), angle.to_radians().sin());
+            let force_field = match label.params.get("kind").copied() {
+                Some("uniform") => ForceField::Uniform {
+                    accel: dir * strength,
+                },
+                Some("radial") => ForceField::Radial { strength },
+                Some("vortex") => ForceField::Vortex { strength },
+                Some("current") => ForceField::Current {
+                    vel: dir * label.param("speed").unwrap_or(0.0),
+                    coeff: strength,
+                },
+                kind => panic!(
+                    "Unknown force field kind {:?} of {}",
+                    kind, label.name
+                ),
+            };
+            ForceZone {
+                transform,
+                global_transform: GlobalTransform::from(transform),
+                name: Name::new(format!("Force {}", label.name)),
+                collider: Collider::nonsolid_from_shapes(shapes.to_vec())
+                    .with_layers(CollisionLayers::new(
+                        layers::FORCE_FIELD,
+                        layers::ALL,
+                    )),
+                force_field,
+            }
+        })
+        .collect()
+}
+
+/// Invisible zone, that changes how dynamic objects move on it
+#[derive(Bundle, Clone)]
+pub struct SurfaceZone {
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+
+    pub name: Name,
+    pub collider: Collider,
+    pub surface: Surface,
+}
+
+/// Surface zones, defined by `surface:<name> friction=<friction_coeff>
+/// accel_scale=<accel_scale> max_vel=<max_vel>` groups of `svg_data`.
+/// All parameters are optional
+fn surface_zones(svg_data: &SvgData, transform: Transform) -> Vec<SurfaceZone> {
+    labeled_groups(svg_data, svgdata::SURFACE_PREFIX)
+        .map(|(label, shapes)| {
+            let mut surface = Surface::new()
+                .with_accel_scale(label.param("accel_scale").unwrap_or(1.0));
+            if let Some(friction_coeff) = label.param("friction") {
+                surface = surface.with_friction_coeff(friction_coeff);
+            }
+            if let Some(max_vel) = label.param("max_vel") {
+                surface = surface.with_max_vel(max_vel);
+            }
+            SurfaceZone {
+                transform,
+                global_transform: GlobalTransform::from(transform),
+                name: Name::new(format!("Surface {}", label.name)),
+                collider: Collider::nonsolid_from_shapes(shapes.to_vec())
+                    .with_layers(CollisionLayers::new(
+                        layers::SURFACE,
+                        layers::ALL,
+                    )),
+                surface,
+            }
+        })
+        .collect()
+}
+
 // Initialize Prefabs
 pub fn initialize_prefabs(
     image_handles: &ImageHandles,
@@ -58,6 +276,9 @@ pub fn initialize_prefabs(
         let depth = depths::PLAYER;
         let max_vel = config::physics::PLAYER_MAX_VEL;
         let friction_coeff = config::physics::PLAYER_FRICTION_COEFF;
+        let angular_friction_coeff =
+            config::physics::PLAYER_ANGULAR_FRICTION_COEFF;
+        let mass = config::physics::PLAYER_MASS;
         let svg_data = svg_datas
             .get(&svg_data_handles.handles[&object_label])
             .unwrap();
@@ -78,10 +299,17 @@ pub fn initialize_prefabs(
                 physics::DynamicObject::from_max_vel_and_friction_coeff(
                     max_vel,
                     friction_coeff,
-                ),
+                )
+                .with_mass(mass)
+                .with_angular_friction_coeff(angular_friction_coeff),
             collider: physics::Collider::solid_from_shapes(
                 svg_data.groups[svgdata::COLLISION].clone(),
-            ),
+            )
+            .with_layers(physics::CollisionLayers::new(
+                layers::PLAYER,
+                layers::ALL,
+            )),
+            interpolation: Interpolation::new(),
         }
     };
 
@@ -111,7 +339,13 @@ pub fn initialize_prefabs(
         }
     };
 
-    let world_map1 = {
+    let (
+        world_map1,
+        world_map1_triggers,
+        world_map1_obstacles,
+        world_map1_force_zones,
+        world_map1_surface_zones,
+    ) = {
         let name = "WorldMap1";
         let object_label = asset::ObjectLabel::WorldMap1;
         let size = sizes::WORLD_MAP1;
@@ -119,10 +353,11 @@ pub fn initialize_prefabs(
         let svg_data = svg_datas
             .get(&svg_data_handles.handles[&object_label])
             .unwrap();
-        WorldMap1 {
+        let transform =
+            Transform::from_xyz(0.0, 0.0, depth).scaled(size / svg_data.size);
+        let world_map1 = WorldMap1 {
             sprite_bundle: SpriteBundle {
-                transform: Transform::from_xyz(0.0, 0.0, depth)
-                    .scaled(size / svg_data.size),
+                transform,
                 sprite: Sprite {
                     custom_size: Some(svg_data.size),
                     ..Default::default()
@@ -131,12 +366,23 @@ pub fn initialize_prefabs(
                 ..Default::default()
             },
             name: Name::new(name),
-        }
+        };
+        (
+            world_map1,
+            trigger_zones(svg_data, transform),
+            obstacles(svg_data, transform),
+            force_zones(svg_data, transform),
+            surface_zones(svg_data, transform),
+        )
     };
 
     Prefabs {
         player,
         princess,
         world_map1,
+        world_map1_triggers,
+        world_map1_obstacles,
+        world_map1_force_zones,
+        world_map1_surface_zones,
     }
 }
@@ -13,6 +13,18 @@ pub fn spawn(
     prefabs: Res<Prefabs>,
 ) {
     commands.spawn_bundle(prefabs.world_map1.clone());
+    for trigger_zone in &prefabs.world_map1_triggers {
+        commands.spawn_bundle(trigger_zone.clone());
+    }
+    for obstacle in &prefabs.world_map1_obstacles {
+        commands.spawn_bundle(obstacle.clone());
+    }
+    for force_zone in &prefabs.world_map1_force_zones {
+        commands.spawn_bundle(force_zone.clone());
+    }
+    for surface_zone in &prefabs.world_map1_surface_zones {
+        commands.spawn_bundle(surface_zone.clone());
+    }
 
     commands.spawn_bundle(prefabs.player.clone());
 
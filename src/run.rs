@@ -1,5 +1,6 @@
 use bevy::prelude::*;
 
+use crate::game::physics::PhysicsLabel;
 use crate::{asset, camera, game, loading, menu, state::AppState};
 
 #[cfg(feature = "debug")]
@@ -26,10 +27,10 @@ pub fn run() {
     // SvgData custom asset and loader
     .add_asset::<asset::SvgData>()
     .init_asset_loader::<asset::svgdata::SvgDataLoader>()
+    // Physics resources, events and systems, running only in the game
+    .add_plugin(game::physics::PhysicsPlugin::in_state(AppState::Game))
     // State //
     .add_state(AppState::Loading)
-    // Events (none for now)
-    // .add_event::<game::spawner::Spawn>()
     /*** SYSTEMS ***/
     // Startup systems
     .add_startup_system(camera::spawn)
@@ -59,27 +60,17 @@ pub fn run() {
     .add_system_set(
         SystemSet::on_update(AppState::Game)
             .label(game::SystemLabel::Input)
+            .before(PhysicsLabel::PreStep)
             .with_system(game::player::input)
+            .with_system(game::player::rotation)
             .with_system(game::exit_press),
     )
-    // Physics
-    .add_system_set(
-        SystemSet::on_update(AppState::Game)
-            .label(game::SystemLabel::Physics)
-            .after(game::SystemLabel::Input)
-            .with_system(game::physics::update),
-    )
     // Camera movement
     .add_system_set(
         SystemSet::on_update(AppState::Game)
-            .after(game::SystemLabel::Physics)
+            .after(PhysicsLabel::PostStep)
             .with_system(camera::movement),
     )
-    // Player rotation mechanic
-    .add_system_set(
-        SystemSet::on_update(AppState::Game)
-            .with_system(game::player::rotation),
-    )
     // Exit
     .add_system_set(SystemSet::on_exit(AppState::Game).with_system(game::exit));
 
@@ -93,13 +84,22 @@ pub fn run() {
         */
 
         app.add_plugin(bevy_inspector_egui::WorldInspectorPlugin::new())
-            .add_system(debug::test_system);
+            .add_system(debug::test_system)
+            .add_system_set(
+                SystemSet::on_update(AppState::Game)
+                    .before(PhysicsLabel::PreStep)
+                    .with_system(debug::physics_time_control),
+            );
 
         // Register components with `bevy_inspector_egui`
         let mut registry = app.world_mut().get_resource_or_insert_with(
             bevy_inspector_egui::InspectableRegistry::default,
         );
         registry.register::<game::physics::DynamicObject>();
+        registry.register::<game::physics::KinematicBody>();
+        registry.register::<game::physics::ForceField>();
+        registry.register::<game::physics::Surface>();
+        registry.register::<game::physics::PhysicsMaterial>();
         //registry.register::<game::physics::Collider>();
     }
 